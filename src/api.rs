@@ -0,0 +1,324 @@
+use crate::authors::{AuthorPost, ProfileImage};
+use crate::mapping::Entity;
+use crate::media::MediaFile;
+use crate::pages::PageData;
+use crate::posts::PostData;
+use crate::tags::TagData;
+use crate::throttle::Throttle;
+use crate::Sink;
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION};
+use reqwest::{Method, StatusCode};
+use reqwest_middleware::ClientWithMiddleware;
+use serde::{Deserialize, Serialize};
+use std::env;
+use tokio::sync::SemaphorePermit;
+use tracing::{error, info, warn};
+
+/// Resposta da API ao criar qualquer entidade; só o id é usado.
+#[derive(Deserialize, Debug)]
+struct ApiReply {
+    id: serde_json::Value,
+}
+
+impl ApiReply {
+    fn remote_id(&self) -> String {
+        match &self.id {
+            serde_json::Value::String(id) => id.clone(),
+            id => id.to_string(),
+        }
+    }
+}
+
+/// Resposta do endpoint de imagens: a URL pública do arquivo.
+#[derive(Deserialize, Debug)]
+struct MediaReply {
+    url: String,
+}
+
+/// O que fazer quando o item já existe na API (mesmo slug ou email).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnConflict {
+    /// Mantém o item remoto e só registra o id no mapeamento.
+    Skip,
+    /// Atualiza o item remoto (PUT).
+    #[default]
+    Update,
+    /// Trata como erro.
+    Fail,
+}
+
+// Id do item de uma busca (`[{..}]`, `{"id": ..}` ou `{"data": [{..}]}`) cujo
+// `key` é exatamente `value`. APIs que ignoram o filtro devolvem uma lista
+// qualquer, e o primeiro item dela não é um conflito.
+fn matching_id(body: &serde_json::Value, key: &str, value: &str) -> Option<String> {
+    let items: Vec<&serde_json::Value> = match body {
+        serde_json::Value::Array(items) => items.iter().collect(),
+        serde_json::Value::Object(fields) if fields.contains_key("id") => vec![body],
+        serde_json::Value::Object(fields) => fields
+            .values()
+            .find_map(|value| value.as_array())?
+            .iter()
+            .collect(),
+        _ => return None,
+    };
+    let item = items
+        .into_iter()
+        .find(|item| item.get(key).and_then(|found| found.as_str()) == Some(value))?;
+    let reply: ApiReply = serde_json::from_value(item.clone()).ok()?;
+    Some(reply.remote_id())
+}
+
+/// Envia os itens para a API configurada em `API_URL`/`API_TOKEN`.
+///
+/// Antes de criar, procura o item pelo slug (ou email, nos autores) e segue
+/// `OnConflict` se ele já existir.
+pub struct ApiSink {
+    client: ClientWithMiddleware,
+    api_url: String,
+    token: String,
+    on_conflict: OnConflict,
+    /// Limite de `--concurrency`/`--rate-limit`, aplicado a cada requisição.
+    throttle: Option<Throttle>,
+}
+
+impl ApiSink {
+    pub fn new(client: ClientWithMiddleware, api_url: String, token: String) -> Self {
+        Self {
+            client,
+            api_url,
+            token,
+            on_conflict: OnConflict::default(),
+            throttle: None,
+        }
+    }
+
+    pub fn with_on_conflict(self, on_conflict: OnConflict) -> Self {
+        Self { on_conflict, ..self }
+    }
+
+    pub fn with_throttle(self, throttle: Throttle) -> Self {
+        Self {
+            throttle: Some(throttle),
+            ..self
+        }
+    }
+
+    // Vaga para uma requisição; um item pode fazer várias (busca, envio, mídias)
+    async fn permit(&self) -> Option<SemaphorePermit<'_>> {
+        match &self.throttle {
+            Some(throttle) => Some(throttle.acquire().await),
+            None => None,
+        }
+    }
+
+    pub fn from_env(client: ClientWithMiddleware) -> Result<Self> {
+        let token = env::var("API_TOKEN").context("Failed to get API_TOKEN from env")?;
+        let api_url = env::var("API_URL").context("Failed to get API_URL from env")?;
+        Ok(Self::new(client, api_url, token))
+    }
+
+    fn headers(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", self.token))
+                .context("Failed to create authorization header")?,
+        );
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        Ok(headers)
+    }
+
+    // Busca o id remoto de um item já existente, ex: GET /posts?slug=...
+    async fn find(&self, path: &str, key: &str, value: &str) -> Result<Option<String>> {
+        let url_req = format!("{}/{}", &self.api_url, path);
+        let _permit = self.permit().await;
+        let response = self
+            .client
+            .get(url_req)
+            .headers(self.headers()?)
+            .query(&[(key, value)])
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        // Endpoint sem busca por slug/email: segue criando, como se não existisse
+        if matches!(
+            response.status(),
+            StatusCode::BAD_REQUEST | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            warn!("lookup {} {}={} not supported ({}), creating", path, key, value, response.status());
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            bail!("lookup {} {}={} failed: {}", path, key, value, response.status());
+        }
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .with_context(|| format!("invalid {} lookup reply", path))?;
+        Ok(matching_id(&body, key, value))
+    }
+
+    async fn upsert<T: Serialize + std::fmt::Debug + Sync>(
+        &self,
+        path: &str,
+        (key, value): (&str, &str),
+        label: &str,
+        name: &str,
+        payload: &T,
+    ) -> Result<Option<String>> {
+        let Some(remote_id) = self.find(path, key, value).await? else {
+            return self.send(Method::POST, path, label, name, payload).await;
+        };
+        match self.on_conflict {
+            OnConflict::Skip => {
+                info!("{} {} already exists as {}, skipping", label, name, remote_id);
+                println!("Já existe ({}): {}", label, name);
+                Ok(Some(remote_id))
+            }
+            OnConflict::Update => {
+                let path = format!("{}/{}", path, remote_id);
+                self.send(Method::PUT, &path, label, name, payload).await
+            }
+            OnConflict::Fail => {
+                eprintln!("Já existe ({}): {} - {}={}", label, name, key, value);
+                Err(anyhow!("{} {} already exists as {}", label, name, remote_id))
+            }
+        }
+    }
+
+    async fn send<T: Serialize + std::fmt::Debug + Sync>(
+        &self,
+        method: Method,
+        path: &str,
+        label: &str,
+        name: &str,
+        payload: &T,
+    ) -> Result<Option<String>> {
+        let url_req = format!("{}/{}", &self.api_url, path);
+        info!("send {} {}: {}", method, label, name);
+        let _permit = self.permit().await;
+        let res = self
+            .client
+            .request(method, url_req)
+            .headers(self.headers()?)
+            .json(payload)
+            .send()
+            .await;
+
+        match res {
+            Ok(response) if response.status().is_success() => {
+                info!("request send");
+                println!("Enviado com sucesso ({}): {}", label, name);
+                let reply: ApiReply = response
+                    .json()
+                    .await
+                    .with_context(|| format!("invalid {} reply", label))?;
+                Ok(Some(reply.remote_id()))
+            }
+            Ok(response) if response.status().is_client_error() => {
+                error!("request client: {:?}", payload);
+                eprintln!("Falha ao enviar {}: {} - Status: {:?}", label, name, response);
+                Err(anyhow!("client error {}", response.status()))
+            }
+            Ok(response) if response.status().is_server_error() => {
+                error!("request server error: {:?}", payload);
+                eprintln!("Falha ao enviar {}: {} - Status: {:?}", label, name, response);
+                Err(anyhow!("server error {}", response.status()))
+            }
+            Ok(response) => {
+                error!("request not mapped error: {:?}", response);
+                Err(anyhow!("request not mapped {}", response.status()))
+            }
+            Err(e) => {
+                error!("request error: {:?}", &e);
+                eprintln!("Erro ao enviar {}: {} - Erro: {:?}", label, name, e);
+                Err(e.into())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for ApiSink {
+    async fn send_author(&self, author: &AuthorPost) -> Result<Option<String>> {
+        let lookup = ("email", author.email.as_str());
+        self.upsert("authors", lookup, "autor", &author.name, author).await
+    }
+
+    async fn send_tag(&self, tag: &TagData) -> Result<Option<String>> {
+        self.upsert("tags", ("slug", &tag.slug), "tag", &tag.name, tag).await
+    }
+
+    async fn send_post(&self, post: &PostData) -> Result<Option<String>> {
+        self.upsert("posts", ("slug", &post.slug), "post", &post.title, post).await
+    }
+
+    async fn send_page(&self, page: &PageData) -> Result<Option<String>> {
+        self.upsert("pages", ("slug", &page.slug), "página", &page.title, page).await
+    }
+
+    async fn update_post(&self, remote_id: &str, post: &PostData) -> Result<Option<String>> {
+        let path = format!("posts/{}", remote_id);
+        self.send(Method::PUT, &path, "post", &post.title, post).await
+    }
+
+    async fn update_page(&self, remote_id: &str, page: &PageData) -> Result<Option<String>> {
+        let path = format!("pages/{}", remote_id);
+        self.send(Method::PUT, &path, "página", &page.title, page).await
+    }
+
+    async fn delete(&self, entity: Entity, remote_id: &str) -> Result<Option<String>> {
+        let url_req = format!("{}/{}/{}", &self.api_url, entity.as_str(), remote_id);
+        info!("delete {}: {}", entity.as_str(), remote_id);
+        let _permit = self.permit().await;
+        let response = self
+            .client
+            .delete(url_req)
+            .headers(self.headers()?)
+            .send()
+            .await?;
+        // Já removido no destino também conta como sucesso
+        if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
+            println!("Removido ({}): {}", entity.as_str(), remote_id);
+            Ok(Some(remote_id.to_string()))
+        } else {
+            eprintln!("Falha ao remover {}: {} - Status: {:?}", entity.as_str(), remote_id, response);
+            Err(anyhow!("delete failed {}", response.status()))
+        }
+    }
+
+    async fn unpublish(&self, entity: Entity, remote_id: &str) -> Result<Option<String>> {
+        let path = format!("{}/{}", entity.as_str(), remote_id);
+        let payload = serde_json::json!({"status": "draft"});
+        self.send(Method::PATCH, &path, entity.as_str(), remote_id, &payload).await
+    }
+
+    async fn send_profile_image(&self, image: &ProfileImage) -> Result<Option<String>> {
+        let path = format!("authors/{}/profile-image", image.author_id);
+        self.send(Method::POST, &path, "imagem de perfil", &image.path_image, image).await
+    }
+
+    async fn upload_media(&self, media: &MediaFile) -> Result<Option<String>> {
+        let url_req = format!("{}/images", &self.api_url);
+        info!("upload media: {}", media.path);
+        let _permit = self.permit().await;
+        let response = self
+            .client
+            .post(url_req)
+            .headers(self.headers()?)
+            .json(media)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            eprintln!("Falha ao enviar mídia: {} - Status: {:?}", media.path, response);
+            bail!("upload {} failed: {}", media.path, response.status());
+        }
+        let reply: MediaReply = response.json().await.context("invalid media reply")?;
+        println!("Enviado com sucesso (mídia): {}", media.path);
+        Ok(Some(reply.url))
+    }
+}
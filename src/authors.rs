@@ -1,41 +1,49 @@
-use anyhow::{Context, Result};
-use dotenv::dotenv;
-use killer::process_image_url;
-use mysql::{prelude::*, Pool};
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION};
-use reqwest::tls::Version;
-use reqwest::Client;
+use crate::mapping::{Entity, IdMap};
+use crate::media::MediaUploader;
+use crate::migrate::{record_reply, MigrateOptions};
+use crate::{process_image_url, Sink, Source};
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::Serialize;
-use std::env;
+use std::sync::Arc;
 use tokio::task;
 use tracing::{error, info};
 
-#[derive(Debug, Serialize)]
-struct AuthorPost {
-    id: i32,
-    name: String,
-    email: String,
-    login: String,
-    password: String,
-    created_at: String,
-    image_url: Option<String>,
+#[derive(Debug, Serialize, Clone)]
+pub struct AuthorPost {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub login: String,
+    /// `user_nicename`, o slug do autor nos arquivos `/author/<nicename>/`.
+    #[serde(skip)]
+    pub nicename: String,
+    /// Papel no WordPress (`administrator`, `editor`, ...); `None` quando a origem não informa.
+    #[serde(skip)]
+    pub role: Option<String>,
+    pub password: String,
+    pub created_at: String,
+    pub image_url: Option<String>,
 }
 
+/// Foto de perfil enviada depois que o autor já existe no destino.
 #[derive(Debug, Serialize)]
-struct ProfileImage {
-    author_id: String,
-    path_image: String,
-    base64: String,
+pub struct ProfileImage {
+    /// Id do autor na API.
+    pub author_id: String,
+    /// Caminho relativo a `content/images`, ex: `2024/01/foto.jpg`.
+    pub path_image: String,
+    pub base64: String,
 }
 
-#[derive(Debug, Serialize)]
-struct AuthorRequest {
-    id: i32,
-    name: String,
-    email: String,
-    login: String,
-    password: String,
-    created_at: String,
+impl ProfileImage {
+    pub fn new(author_id: &str, path_image: &str, bytes: &[u8]) -> Self {
+        Self {
+            author_id: author_id.to_string(),
+            path_image: path_image.to_string(),
+            base64: STANDARD.encode(bytes),
+        }
+    }
 }
 
 impl AuthorPost {
@@ -47,162 +55,92 @@ impl AuthorPost {
     }
 }
 
-async fn send_author(client: Client, author_data: AuthorPost) -> Result<(), String> {
-    dotenv().ok();
-    let token = env::var("API_TOKEN")
-        .context("Failed to get API_TOKEN from env")
-        .unwrap();
-    let api_url = env::var("API_URL")
-        .context("Failed to get API_URL from env")
-        .unwrap();
-    let url_req = format!("{}/authors", &api_url);
-    println!("send author: { }", author_data.name);
-
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", token))
-            .context("Failed to create authorization header")
-            .unwrap(),
-    );
-    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
-    info!("config request");
-
-    let res = client
-        .post(url_req)
-        .headers(headers)
-        .json(&author_data)
-        .send()
-        .await;
-
-    match res {
-        Ok(response) if response.status().is_success() => {
-            info!("request send");
-            println!("Autor enviado com sucesso: {}", author_data.name);
-            Ok(())
-        }
-        Ok(response) if response.status().is_client_error() => {
-            error!("request client: {:?}", &author_data);
-            eprintln!(
-                "Falha ao enviar autor: {} - Status: {:?}",
-                author_data.name, response
-            );
-            Err(String::from("Fail to env"))
-        }
-        Ok(response) if response.status().is_server_error() => {
-            error!("request server error: {:?}", &author_data);
-            eprintln!(
-                "Falha ao enviar autor: {} - Status: {:?}",
-                author_data.name, response
-            );
-            Err(String::from("Fail to env"))
-        }
-
-        Ok(_) => {
-            error!("request not mapped error: {:?}", &res);
-            Err(String::from("request not mapped"))
-        }
-        Err(e) => {
-            error!("request error: {:?}", &e);
-            eprintln!("Erro ao enviar autor: {} - Erro: {:?}", author_data.name, e);
-            Err(String::from("error request"))
+/// `user_nicename` como o WordPress gera a partir do login: minúsculas, sem
+/// acentos, `.` e espaços viram `-` e os outros símbolos (ex: `@`) somem.
+pub fn nicename(login: &str) -> String {
+    let mut slug = String::new();
+    for c in login.trim().to_lowercase().chars() {
+        let c = match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            '.' => '-',
+            c if c.is_whitespace() => '-',
+            c => c,
+        };
+        if c.is_ascii_alphanumeric() || c == '_' || (c == '-' && !slug.is_empty() && !slug.ends_with('-')) {
+            slug.push(c);
         }
     }
+    slug.trim_end_matches('-').to_string()
 }
 
-async fn get_authors() -> Result<Vec<AuthorPost>, String> {
-    dotenv().ok();
-    let db_url = env::var("DB_URL")
-        .context("Failed to get DB_URL from env")
-        .unwrap();
-    let connection_opts = mysql::Opts::from_url(&db_url)
-        .context("Failed to parse DB_URL")
-        .unwrap();
-    let pool = Pool::new(connection_opts)
-        .context("Failed to create connection pool")
-        .unwrap();
-    let mut conn = pool
-        .get_conn()
-        .context("Failed to get connection from pool")
-        .unwrap();
+/// Aplica as transformações, gerando o autor como será enviado.
+pub fn prepare_author(author: AuthorPost) -> AuthorPost {
+    let image_right = if let Some(image_url) = &author.image_url {
+        process_image_url(image_url)
+    } else {
+        String::from("")
+    };
+    author.update_image(image_right)
+}
 
-    let result_query_authors = conn
-        .query_map(
-            "SELECT DISTINCT
-                    u.ID AS id,
-                    u.display_name AS name,
-                    u.user_email AS email,
-                    u.user_login AS login,
-                    u.user_pass AS password,
-                    u.user_registered AS created_at,
-                    (SELECT meta_value FROM wp_usermeta WHERE user_id = u.ID AND meta_key = 'molongui_author_image_url') AS profile_image_url
-                FROM
-                    wp_users u
-                JOIN
-                    wp_posts p ON u.ID = p.post_author
-                LEFT JOIN
-                    wp_usermeta um ON u.ID = um.user_id
-                WHERE
-                    p.post_type = 'post' AND
-                    p.post_status = 'publish' AND
-                    u.user_email IS NOT NULL AND
-                    u.user_email <> ''
-                ",
-            |(id, name, email, login, password, created_at, profile_image_url): (i32, String, String, String, String, String, Option<String>)|
-            AuthorPost {
-                id,
-                name,
-                email,
-                login,
-                password,
-                created_at,
-                image_url: profile_image_url,
-            },
-        );
-    match result_query_authors {
-        Ok(res) => {
-            let authors: Vec<AuthorPost> = res;
-            info!("ok query authors");
-            return Ok(authors);
-        }
-        Err(message) => {
-            error!("Fail to query author: {}", message);
-            Err(String::from("fail to query author"))
-        }
+// Lê a foto já reescrita por `prepare_author` e envia vinculada ao autor remoto,
+// uma vez por imagem: numa nova execução só vai se o autor trocou de foto
+async fn send_profile_image(
+    sink: &dyn Sink,
+    media: &MediaUploader,
+    id_map: &IdMap,
+    author: &AuthorPost,
+    remote_id: &str,
+) -> Result<()> {
+    let image_url = author.image_url.as_deref().unwrap_or_default();
+    let Some(path_image) = image_url.strip_prefix("/content/images/") else {
+        return Ok(());
+    };
+    if id_map.get(Entity::ProfileImages, author.id).as_deref() == Some(path_image) {
+        info!("profile image of author {} already sent, skipping", author.id);
+        return Ok(());
     }
+    let bytes = media.fetch(path_image).await?;
+    sink.send_profile_image(&ProfileImage::new(remote_id, path_image, &bytes)).await?;
+    id_map.insert(Entity::ProfileImages, author.id, path_image)?;
+    Ok(())
 }
 
-pub async fn migrate_authors() {
-    match get_authors().await {
-        Ok(authors) => {
-            info!("found {} authors from database", authors.len());
-            let client = Client::builder()
-                .min_tls_version(Version::TLS_1_2)
-                .danger_accept_invalid_certs(true)
-                .build()
-                .unwrap();
+pub async fn migrate_authors(
+    source: &dyn Source,
+    sink: Arc<dyn Sink>,
+    id_map: Arc<IdMap>,
+    options: &MigrateOptions,
+) {
+    match source.authors(&options.filter).await {
+        Ok(mut authors) => {
+            info!("found {} authors from source", authors.len());
+            options.skip_migrated(&id_map, Entity::Authors, &mut authors, |author| author.id);
+            let throttle = Arc::new(options.throttle());
             let mut handles = vec![];
 
             for author in authors {
-                let client_clone = client.clone();
+                let sink = sink.clone();
+                let id_map = id_map.clone();
+                let throttle = throttle.clone();
+                let media = options.media.clone();
 
                 let handle = task::spawn(async move {
-                    let image_right = if let Some(image_url) = &author.image_url {
-                        process_image_url(image_url)
-                    } else {
-                        String::from("")
-                    };
-                    let author_change = AuthorPost {
-                        image_url: Some(image_right),
-                        ..author
-                    };
-
-                    match send_author(client_clone, author_change).await {
-                        Ok(_) => {
-                            info!("Author updated successfully");
-                        }
-                        Err(e) => {
-                            error!("Failed to update author {:?}", e);
+                    let _permit = throttle.acquire().await;
+                    let author_change = prepare_author(author);
+                    let reply = sink.send_author(&author_change).await;
+                    let remote_id = reply.as_ref().ok().cloned().flatten();
+                    record_reply(&id_map, Entity::Authors, author_change.id, reply);
+                    // A foto só pode ser enviada depois que o autor existe no destino
+                    if let (Some(media), Some(remote_id)) = (media, remote_id) {
+                        if let Err(e) = send_profile_image(sink.as_ref(), &media, &id_map, &author_change, &remote_id).await {
+                            error!("Fail to send profile image of author {}: {:?}", author_change.id, e);
                         }
                     }
                 });
@@ -1,4 +1,25 @@
-use clap::{Parser, Subcommand};
+use crate::health::test_db_connection;
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use killer::api::{ApiSink, OnConflict};
+use killer::dry_run::DryRun;
+use killer::filter::{Filter, PostStatus};
+use killer::http::RetryConfig;
+use killer::media::MediaUploader;
+use killer::migrate::MigrateOptions;
+use killer::mysql_source::MysqlSource;
+use killer::posts::HtmlOptions;
+use killer::prune::PruneAction;
+use killer::redirects::RedirectOptions;
+use killer::tags::{Hierarchy, TagMapping, TagRole};
+use killer::throttle::Throttle;
+use killer::wxr::WxrDocument;
+use killer::{Sink, Source};
+use reqwest_middleware::ClientWithMiddleware;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[derive(Debug, Parser)]
 #[command(name = "Killer WordPress")]
@@ -6,18 +27,547 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    #[command(flatten)]
+    pub retry: RetryArgs,
+    #[command(flatten)]
+    pub source: SourceArgs,
+    #[command(flatten)]
+    pub tags: TagMappingArgs,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SourceKind {
+    /// WordPress database from DB_URL
+    Mysql,
+    /// WordPress export file (Tools → Export)
+    Wxr,
+}
+
+#[derive(Debug, Args)]
+pub struct SourceArgs {
+    /// Where to read the WordPress content from
+    #[arg(long, global = true, value_enum, default_value_t = SourceKind::Mysql)]
+    pub source: SourceKind,
+    /// WXR file used with --source wxr
+    #[arg(long, global = true, required_if_eq("source", "wxr"))]
+    pub file: Option<PathBuf>,
+    /// Multisite: read posts, tags and pages from this site (see `sites`)
+    #[arg(long, global = true, value_parser = clap::value_parser!(u64).range(1..))]
+    pub blog_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TagRoleArg {
+    /// First tags of the post
+    Primary,
+    /// Tags after the primary ones
+    Secondary,
+    /// Internal tags (#name), hidden from the site
+    Internal,
+    /// Not migrated
+    Skip,
+}
+
+impl From<TagRoleArg> for TagRole {
+    fn from(role: TagRoleArg) -> Self {
+        match role {
+            TagRoleArg::Primary => TagRole::Primary,
+            TagRoleArg::Secondary => TagRole::Secondary,
+            TagRoleArg::Internal => TagRole::Internal,
+            TagRoleArg::Skip => TagRole::Skip,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum HierarchyArg {
+    /// Send the parent category id
+    Parent,
+    /// Join the parent names into the tag name ("Internacional / Europa")
+    Flatten,
+}
+
+#[derive(Debug, Args)]
+pub struct TagMappingArgs {
+    /// How WordPress categories are migrated
+    #[arg(long, global = true, value_enum, default_value_t = TagRoleArg::Primary)]
+    pub categories_as: TagRoleArg,
+    /// How WordPress tags (post_tag) are migrated
+    #[arg(long, global = true, value_enum, default_value_t = TagRoleArg::Secondary)]
+    pub post_tags_as: TagRoleArg,
+    /// How nested categories are migrated
+    #[arg(long, global = true, value_enum, default_value_t = HierarchyArg::Parent)]
+    pub category_hierarchy: HierarchyArg,
+}
+
+impl TagMappingArgs {
+    pub fn mapping(&self) -> TagMapping {
+        TagMapping {
+            categories: self.categories_as.into(),
+            post_tags: self.post_tags_as.into(),
+            hierarchy: match self.category_hierarchy {
+                HierarchyArg::Parent => Hierarchy::Parent,
+                HierarchyArg::Flatten => Hierarchy::Flatten,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct RetryArgs {
+    /// Retries for transient api errors (5xx, 429 and connection errors)
+    #[arg(long, global = true, default_value_t = 3)]
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds, doubled on each attempt
+    #[arg(long, global = true, default_value_t = 500)]
+    pub retry_min_backoff_ms: u64,
+    /// Maximum delay between retries in milliseconds
+    #[arg(long, global = true, default_value_t = 30_000)]
+    pub retry_max_backoff_ms: u64,
+}
+
+impl RetryArgs {
+    pub fn config(&self) -> RetryConfig {
+        let min_backoff = Duration::from_millis(self.retry_min_backoff_ms);
+        RetryConfig {
+            max_retries: self.max_retries,
+            min_backoff,
+            max_backoff: Duration::from_millis(self.retry_max_backoff_ms).max(min_backoff),
+        }
+    }
 }
 
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     /// Test Connections
-    Test,   
+    Test,
     /// Migration Authors
-    Authors,
+    Authors(AuthorArgs),
     /// Migration Tags
-    Tags,
+    Tags(MigrateArgs),
     /// Migration Posts
-    Posts,
+    Posts(ContentArgs),
     /// Migration Pages
-    Pages
-}
\ No newline at end of file
+    Pages(ContentArgs),
+    /// Send what changed since the last sync: new tags and authors, and
+    /// posts and pages modified since then (updating the ones already migrated)
+    Sync(ContentArgs),
+    /// List the sites of a multisite network
+    Sites,
+    /// Export everything into a Ghost import json file
+    Export {
+        /// Path of the generated file
+        #[arg(long, short, default_value = "ghost-import.json")]
+        output: PathBuf,
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[command(flatten)]
+        html: HtmlArgs,
+    },
+    /// Copy wp-content/uploads into a content/images tree and write a manifest
+    Media {
+        /// WordPress `wp-content/uploads` directory
+        #[arg(long)]
+        uploads_dir: PathBuf,
+        /// Target `content/images` directory
+        #[arg(long, short, default_value = "content/images")]
+        output: PathBuf,
+        /// Path of the old -> new paths manifest
+        #[arg(long, default_value = "media-manifest.json")]
+        manifest: PathBuf,
+        /// Only copy files referenced by the selected posts and pages
+        #[arg(long)]
+        referenced_only: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Write redirects from the old WordPress permalinks: Ghost redirects.yaml
+    /// and redirects.json, an nginx map and an Apache .htaccess
+    Redirects {
+        /// Directory of the generated files
+        #[arg(long, short, default_value = "redirects")]
+        output: PathBuf,
+        #[command(flatten)]
+        redirects: RedirectArgs,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Unpublish or delete migrated posts and pages that were trashed or
+    /// deleted in WordPress
+    Prune(PruneArgs),
+}
+
+impl Commands {
+    /// Comandos que leem ou gravam o MAPPING_FILE.
+    pub fn uses_mapping(&self) -> bool {
+        matches!(
+            self,
+            Commands::Authors(_)
+                | Commands::Tags(_)
+                | Commands::Posts(_)
+                | Commands::Pages(_)
+                | Commands::Sync(_)
+                | Commands::Prune(_)
+        )
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct MigrateArgs {
+    /// Skip items already migrated by a previous run (see MAPPING_FILE)
+    #[arg(long)]
+    pub resume: bool,
+    /// Maximum number of api requests in flight at the same time (lookups, sends and media uploads)
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    pub concurrency: u32,
+    /// Maximum number of api requests started per second
+    #[arg(long, value_name = "REQ/S", value_parser = parse_rate_limit)]
+    pub rate_limit: Option<f64>,
+    /// Run queries and transformations but print the payloads instead of sending them
+    #[arg(long)]
+    pub dry_run: bool,
+    /// With --dry-run, write one json file per payload into this directory instead of stdout
+    #[arg(long, requires = "dry_run")]
+    pub output_dir: Option<PathBuf>,
+    /// What to do when the item already exists in the api (same slug, or email for authors)
+    #[arg(long, value_enum, default_value_t = OnConflictArg::Update)]
+    pub on_conflict: OnConflictArg,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OnConflictArg {
+    /// Keep the remote item and only record its id
+    Skip,
+    /// Update the remote item
+    Update,
+    /// Report an error
+    Fail,
+}
+
+impl From<OnConflictArg> for OnConflict {
+    fn from(on_conflict: OnConflictArg) -> Self {
+        match on_conflict {
+            OnConflictArg::Skip => OnConflict::Skip,
+            OnConflictArg::Update => OnConflict::Update,
+            OnConflictArg::Fail => OnConflict::Fail,
+        }
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct PruneArgs {
+    /// What to do with the remote items
+    #[arg(long, value_enum, default_value_t = PruneActionArg::Unpublish)]
+    pub action: PruneActionArg,
+    /// Skip the confirmation prompt
+    #[arg(long, short)]
+    pub yes: bool,
+    /// Only list what would be pruned
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Also prune items missing from the source when it is a WXR file or when
+    /// most of the mapping looks missing
+    #[arg(long)]
+    pub allow_missing: bool,
+}
+
+impl PruneArgs {
+    pub fn sink(&self, client: ClientWithMiddleware) -> anyhow::Result<Arc<dyn Sink>> {
+        if self.dry_run {
+            Ok(Arc::new(DryRun::new(None)))
+        } else {
+            Ok(Arc::new(ApiSink::from_env(client)?))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PruneActionArg {
+    /// Move the remote item back to draft
+    Unpublish,
+    /// Delete the remote item and forget its id
+    Delete,
+}
+
+impl From<PruneActionArg> for PruneAction {
+    fn from(action: PruneActionArg) -> Self {
+        match action {
+            PruneActionArg::Unpublish => PruneAction::Unpublish,
+            PruneActionArg::Delete => PruneAction::Delete,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StatusArg {
+    Publish,
+    Draft,
+    Future,
+    Private,
+    Pending,
+}
+
+impl From<StatusArg> for PostStatus {
+    fn from(status: StatusArg) -> Self {
+        match status {
+            StatusArg::Publish => PostStatus::Publish,
+            StatusArg::Draft => PostStatus::Draft,
+            StatusArg::Future => PostStatus::Future,
+            StatusArg::Private => PostStatus::Private,
+            StatusArg::Pending => PostStatus::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct FilterArgs {
+    /// WordPress statuses to migrate, comma separated
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [StatusArg::Publish])]
+    pub status: Vec<StatusArg>,
+    /// Only posts with an image (<img) in the content
+    #[arg(long)]
+    pub require_image: bool,
+    /// Only posts with at least one category
+    #[arg(long)]
+    pub require_category: bool,
+    /// Only these post ids, comma separated (authors: authors of these posts)
+    #[arg(long, value_delimiter = ',')]
+    pub id: Vec<u64>,
+    /// Only posts published on or after this day (YYYY-MM-DD)
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_day)]
+    pub since: Option<NaiveDate>,
+    /// Only posts published on or before this day (YYYY-MM-DD)
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_day)]
+    pub until: Option<NaiveDate>,
+    /// Only posts by this WordPress user id
+    #[arg(long)]
+    pub author: Option<u64>,
+    /// Only posts in this category slug (posts only)
+    #[arg(long)]
+    pub category: Option<String>,
+}
+
+fn parse_day(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("`{}` is not a date in the YYYY-MM-DD format", value))
+}
+
+impl FilterArgs {
+    pub fn filter(&self) -> Filter {
+        Filter {
+            statuses: self.status.iter().map(|status| (*status).into()).collect(),
+            require_image: self.require_image,
+            require_category: self.require_category,
+            ids: self.id.clone(),
+            since: self.since,
+            until: self.until,
+            author: self.author,
+            category: self.category.clone(),
+            modified_since: None,
+        }
+    }
+}
+
+/// Migração de autores: filtros e envio das fotos, sem as opções de HTML.
+#[derive(Debug, Args, Clone)]
+pub struct AuthorArgs {
+    #[command(flatten)]
+    pub migrate: MigrateArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    #[command(flatten)]
+    pub media: MediaArgs,
+}
+
+impl AuthorArgs {
+    pub fn options(&self, tags: &TagMappingArgs, client: &ClientWithMiddleware) -> MigrateOptions {
+        MigrateOptions {
+            filter: self.filter.filter(),
+            media: self.media.uploader(client, self.migrate.dry_run),
+            ..self.migrate.options(tags)
+        }
+    }
+}
+
+/// Migração de posts e páginas, que aceitam filtros e reescrevem o HTML.
+#[derive(Debug, Args, Clone)]
+pub struct ContentArgs {
+    #[command(flatten)]
+    pub migrate: MigrateArgs,
+    #[command(flatten)]
+    pub filter: FilterArgs,
+    #[command(flatten)]
+    pub media: MediaArgs,
+    #[command(flatten)]
+    pub html: HtmlArgs,
+}
+
+impl ContentArgs {
+    pub fn options(&self, tags: &TagMappingArgs, client: &ClientWithMiddleware) -> MigrateOptions {
+        MigrateOptions {
+            filter: self.filter.filter(),
+            media: self.media.uploader(client, self.migrate.dry_run),
+            html: self.html.options(),
+            ..self.migrate.options(tags)
+        }
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct HtmlArgs {
+    /// Point WordPress thumbnails (`-300x200.jpg`) at the original file and drop `srcset`
+    #[arg(long)]
+    pub collapse_image_sizes: bool,
+    /// Rewrite links between posts and pages (by slug, ?p=ID or guid) to this url format
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "/{slug}/")]
+    pub rewrite_links: Option<String>,
+    /// Old domain of the blog whose links are also internal (repeatable); guid domains are always included
+    #[arg(long, value_name = "HOST", requires = "rewrite_links")]
+    pub link_host: Vec<String>,
+}
+
+impl HtmlArgs {
+    pub fn options(&self) -> HtmlOptions {
+        HtmlOptions {
+            collapse_sizes: self.collapse_image_sizes,
+            link_format: self.rewrite_links.clone(),
+            link_hosts: self.link_host.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct RedirectArgs {
+    /// New url of each post and page
+    #[arg(long, value_name = "FORMAT", default_value = "/{slug}/")]
+    pub format: String,
+    /// New url of each category archive
+    #[arg(long, value_name = "FORMAT", default_value = "/tag/{slug}/")]
+    pub category_format: String,
+    /// New url of each tag archive
+    #[arg(long, value_name = "FORMAT", default_value = "/tag/{slug}/")]
+    pub tag_format: String,
+    /// New url of each author archive, by user_nicename
+    #[arg(long, value_name = "FORMAT", default_value = "/author/{slug}/")]
+    pub author_format: String,
+    /// Use this instead of the permalink_structure option (required with --source wxr)
+    #[arg(long, value_name = "STRUCTURE")]
+    pub permalink_structure: Option<String>,
+    /// Use this instead of the category_base option
+    #[arg(long, value_name = "BASE")]
+    pub category_base: Option<String>,
+    /// Use this instead of the tag_base option
+    #[arg(long, value_name = "BASE")]
+    pub tag_base: Option<String>,
+}
+
+impl RedirectArgs {
+    pub fn options(&self) -> RedirectOptions {
+        RedirectOptions {
+            structure: self.permalink_structure.clone(),
+            category_base: self.category_base.clone(),
+            tag_base: self.tag_base.clone(),
+            post_format: self.format.clone(),
+            category_format: self.category_format.clone(),
+            tag_format: self.tag_format.clone(),
+            author_format: self.author_format.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct MediaArgs {
+    /// Upload referenced images and PDFs to the api and use the returned urls
+    #[arg(long)]
+    pub upload_media: bool,
+    /// WordPress `wp-content/uploads` directory to read the files from
+    #[arg(long, requires = "upload_media")]
+    pub uploads_dir: Option<PathBuf>,
+    /// WordPress address to download the files not found in --uploads-dir
+    #[arg(long, value_name = "URL", requires = "upload_media")]
+    pub media_base_url: Option<String>,
+}
+
+impl MediaArgs {
+    /// Nada é enviado no dry-run, então as mídias nem são lidas.
+    pub fn uploader(&self, client: &ClientWithMiddleware, dry_run: bool) -> Option<Arc<MediaUploader>> {
+        if !self.upload_media || dry_run {
+            return None;
+        }
+        let uploader = MediaUploader::new(client.clone(), self.uploads_dir.clone(), self.media_base_url.clone());
+        Some(Arc::new(uploader))
+    }
+}
+
+fn parse_rate_limit(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("`{}` is not a positive number of requests per second", value)),
+    }
+}
+
+impl SourceArgs {
+    pub fn mysql(&self) -> anyhow::Result<MysqlSource> {
+        let source = MysqlSource::from_env()?;
+        Ok(match self.blog_id {
+            Some(blog_id) => source.with_blog_id(blog_id),
+            None => source,
+        })
+    }
+
+    pub fn open(&self) -> anyhow::Result<Box<dyn Source>> {
+        match self.source {
+            SourceKind::Mysql => Ok(Box::new(self.mysql()?)),
+            SourceKind::Wxr => {
+                if self.blog_id.is_some() {
+                    warn!("--blog-id is ignored with --source wxr, export the site itself");
+                }
+                let file = self
+                    .file
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("--file is required with --source wxr"))?;
+                let document = WxrDocument::from_file(file)?;
+                info!(
+                    "WXR loaded: {} authors, {} categories, {} items",
+                    document.authors.len(),
+                    document.categories.len(),
+                    document.items.len()
+                );
+                Ok(Box::new(document))
+            }
+        }
+    }
+
+    pub async fn test_connection(&self) {
+        if let SourceKind::Mysql = self.source {
+            let _ = test_db_connection().await;
+        }
+    }
+}
+
+impl MigrateArgs {
+    pub fn options(&self, tags: &TagMappingArgs) -> MigrateOptions {
+        MigrateOptions {
+            resume: self.resume,
+            concurrency: self.concurrency as usize,
+            tag_mapping: tags.mapping(),
+            filter: Filter::default(),
+            sync: false,
+            media: None,
+            html: HtmlOptions::default(),
+        }
+    }
+
+    /// Com `--dry-run` os payloads vão para o stdout ou `--output-dir`, senão para a API.
+    pub fn sink(&self, client: ClientWithMiddleware) -> anyhow::Result<Arc<dyn Sink>> {
+        if self.dry_run {
+            Ok(Arc::new(DryRun::new(self.output_dir.clone())))
+        } else {
+            let throttle = Throttle::new(self.concurrency as usize, self.rate_limit);
+            let sink = ApiSink::from_env(client)?
+                .with_on_conflict(self.on_conflict.into())
+                .with_throttle(throttle);
+            Ok(Arc::new(sink))
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::authors::{AuthorPost, ProfileImage};
+use crate::mapping::Entity;
+use crate::media::MediaFile;
+use crate::pages::PageData;
+use crate::posts::PostData;
+use crate::tags::TagData;
+use crate::Sink;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+/// Destino dos payloads quando a migração roda com `--dry-run`: em vez de
+/// enviar para a API, o JSON que seria enviado é impresso ou gravado em disco.
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    output_dir: Option<PathBuf>,
+}
+
+impl DryRun {
+    /// Sem diretório, os payloads vão para o stdout.
+    pub fn new(output_dir: Option<PathBuf>) -> Self {
+        Self { output_dir }
+    }
+
+    /// Grava o payload em `<output_dir>/<entidade>/<wp_id>.json`, ou imprime no stdout.
+    pub fn write<T: Serialize>(&self, entity: Entity, wp_id: impl ToString, payload: &T) -> Result<()> {
+        let content = serde_json::to_string_pretty(payload).context("Failed to serialize payload")?;
+        match &self.output_dir {
+            Some(output_dir) => {
+                let dir = output_dir.join(entity.as_str());
+                fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+                let path = dir.join(format!("{}.json", wp_id.to_string()));
+                fs::write(&path, content)
+                    .with_context(|| format!("Failed to write payload {}", path.display()))
+            }
+            None => {
+                println!("{}", content);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// No dry-run nada é criado, então nenhum id remoto é devolvido.
+#[async_trait]
+impl Sink for DryRun {
+    async fn send_author(&self, author: &AuthorPost) -> Result<Option<String>> {
+        self.write(Entity::Authors, author.id, author).map(|_| None)
+    }
+
+    async fn send_tag(&self, tag: &TagData) -> Result<Option<String>> {
+        self.write(Entity::Tags, tag.id, tag).map(|_| None)
+    }
+
+    async fn send_post(&self, post: &PostData) -> Result<Option<String>> {
+        self.write(Entity::Posts, post.id, post).map(|_| None)
+    }
+
+    async fn send_page(&self, page: &PageData) -> Result<Option<String>> {
+        self.write(Entity::Pages, page.id, page).map(|_| None)
+    }
+
+    async fn update_post(&self, _remote_id: &str, post: &PostData) -> Result<Option<String>> {
+        self.write(Entity::Posts, post.id, post).map(|_| None)
+    }
+
+    async fn update_page(&self, _remote_id: &str, page: &PageData) -> Result<Option<String>> {
+        self.write(Entity::Pages, page.id, page).map(|_| None)
+    }
+
+    async fn delete(&self, entity: Entity, remote_id: &str) -> Result<Option<String>> {
+        let action = json!({"action": "delete", "remote_id": remote_id});
+        self.write(entity, format!("delete-{}", remote_id), &action).map(|_| None)
+    }
+
+    async fn unpublish(&self, entity: Entity, remote_id: &str) -> Result<Option<String>> {
+        let action = json!({"action": "unpublish", "remote_id": remote_id});
+        self.write(entity, format!("unpublish-{}", remote_id), &action).map(|_| None)
+    }
+
+    async fn send_profile_image(&self, image: &ProfileImage) -> Result<Option<String>> {
+        self.write(Entity::Authors, format!("{}-profile-image", image.author_id), image).map(|_| None)
+    }
+
+    // Arquivos não são enviados no dry-run; o HTML mantém os caminhos locais
+    async fn upload_media(&self, _media: &MediaFile) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
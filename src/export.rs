@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use killer::authors::{nicename, AuthorPost};
+use killer::filter::{Filter, PostStatus};
+use killer::ghost::{ghost_role, to_ghost_date, GhostData, GhostPost, GhostTag, GhostUser};
+use killer::links::LinkRewriter;
+use killer::pages::{prepare_page_with_links, PageData};
+use killer::posts::{prepare_post_with_links, HtmlOptions, PostData};
+use killer::tags::{category_paths, flatten_terms, Hierarchy, TagData, TagMapping};
+use killer::{process_image_url, Source};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tracing::{error, info};
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn ghost_user(author: &AuthorPost) -> GhostUser {
+    GhostUser {
+        id: author.id.to_string(),
+        name: author.name.clone(),
+        slug: nicename(&author.nicename),
+        email: author.email.clone(),
+        profile_image: author.image_url.as_deref().map(process_image_url),
+        created_at: to_ghost_date(&author.created_at),
+    }
+}
+
+fn ghost_tag(tag: &TagData) -> GhostTag {
+    GhostTag {
+        id: tag.id.to_string(),
+        name: tag.name.clone(),
+        slug: tag.slug.clone(),
+        description: non_empty(&tag.description),
+    }
+}
+
+// O Ghost só tem publicado, agendado e rascunho
+fn ghost_status(status: PostStatus) -> &'static str {
+    match status {
+        PostStatus::Publish => "published",
+        PostStatus::Future => "scheduled",
+        PostStatus::Draft | PostStatus::Pending | PostStatus::Private => "draft",
+    }
+}
+
+fn published_at(status: PostStatus, date: &str) -> Option<String> {
+    matches!(status, PostStatus::Publish | PostStatus::Future).then(|| to_ghost_date(date))
+}
+
+fn ghost_post(post: &PostData) -> GhostPost {
+    GhostPost {
+        id: post.id.to_string(),
+        title: post.title.clone(),
+        slug: post.slug.clone(),
+        html: post.html.clone(),
+        custom_excerpt: non_empty(&post.excerpt),
+        feature_image: post.image_url.as_deref().and_then(non_empty),
+        post_type: String::from("post"),
+        status: String::from(ghost_status(post.status)),
+        created_at: to_ghost_date(&post.created_at),
+        updated_at: to_ghost_date(&post.updated_at),
+        published_at: published_at(post.status, &post.created_at),
+    }
+}
+
+fn ghost_page(page: &PageData) -> GhostPost {
+    GhostPost {
+        id: page.id.to_string(),
+        title: page.title.clone(),
+        slug: page.slug.clone(),
+        html: page.html.clone(),
+        custom_excerpt: non_empty(&page.excerpt),
+        feature_image: page.image_url.as_deref().and_then(non_empty),
+        post_type: String::from("page"),
+        status: String::from(ghost_status(page.status)),
+        created_at: to_ghost_date(&page.created_at),
+        updated_at: to_ghost_date(&page.updated_at),
+        published_at: published_at(page.status, &page.created_at),
+    }
+}
+
+async fn build_export(
+    source: &dyn Source,
+    mapping: &TagMapping,
+    filter: &Filter,
+    html: &HtmlOptions,
+) -> Result<GhostData> {
+    let authors = source.authors(filter).await?;
+    let mut tags = source.tags().await?;
+    let posts = source.posts(filter).await?;
+    let pages = source.pages(filter).await?;
+    info!(
+        "exporting {} authors, {} tags, {} posts and {} pages",
+        authors.len(),
+        tags.len(),
+        posts.len(),
+        pages.len()
+    );
+
+    // O Ghost não tem tags aninhadas, então o pai só aparece no modo flatten
+    if mapping.hierarchy == Hierarchy::Flatten {
+        let paths = category_paths(&tags);
+        flatten_terms(&mut tags, &paths);
+    }
+    let mut data = GhostData {
+        tags: tags
+            .into_iter()
+            .filter_map(|tag| mapping.apply(tag))
+            .map(|tag| ghost_tag(&tag))
+            .collect(),
+        ..GhostData::default()
+    };
+    for author in &authors {
+        data.add_user(ghost_user(author), ghost_role(author.role.as_deref()));
+    }
+    // Só relaciona autores presentes no arquivo, o importador rejeita ids desconhecidos
+    let user_ids: HashSet<String> = data.users.iter().map(|user| user.id.clone()).collect();
+    let known_author = |author_id: &str| user_ids.get(author_id).cloned();
+
+    // Os posts e páginas exportados já formam o índice dos links internos
+    let links = html.link_format.as_deref().map(|format| {
+        let mut links = LinkRewriter::new(format);
+        posts.iter().for_each(|post| links.add(post.id, &post.slug, &post.guid));
+        pages.iter().for_each(|page| links.add(page.id, &page.slug, ""));
+        html.link_hosts.iter().for_each(|host| links.add_host(host));
+        links
+    });
+
+    for post in posts {
+        let post = prepare_post_with_links(post, mapping, html, links.as_ref());
+        let post_tag_ids = mapping
+            .order_terms(&post.terms)
+            .iter()
+            .map(|tag| tag.id.to_string())
+            .collect();
+        data.add_post(ghost_post(&post), known_author(&post.author_id), post_tag_ids);
+    }
+    for page in pages {
+        let page = prepare_page_with_links(page, html, links.as_ref());
+        data.add_post(ghost_page(&page), known_author(&page.author_id), vec![]);
+    }
+
+    Ok(data)
+}
+
+/// Gera um único arquivo de importação do Ghost com autores, tags, posts e páginas.
+pub async fn export_ghost(
+    source: &dyn Source,
+    mapping: &TagMapping,
+    filter: &Filter,
+    html: &HtmlOptions,
+    output: &Path,
+) {
+    let result = async {
+        let data = build_export(source, mapping, filter, html).await?;
+        let content = serde_json::to_string_pretty(&data.into_import())
+            .context("Failed to serialize ghost import")?;
+        fs::write(output, content)
+            .with_context(|| format!("Failed to write {}", output.display()))
+    }
+    .await;
+
+    match result {
+        Ok(()) => info!("ghost import written to {}", output.display()),
+        Err(e) => error!("Fail to export ghost import: {:?}", e),
+    }
+}
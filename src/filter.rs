@@ -0,0 +1,122 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Status de um post ou página no WordPress.
+///
+/// No payload vai o nome usado no destino (`publish` vira `published`,
+/// `future` vira `scheduled`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum PostStatus {
+    #[default]
+    #[serde(rename = "published")]
+    Publish,
+    #[serde(rename = "draft")]
+    Draft,
+    #[serde(rename = "scheduled")]
+    Future,
+    #[serde(rename = "private")]
+    Private,
+    #[serde(rename = "pending")]
+    Pending,
+}
+
+impl PostStatus {
+    /// Valor de `wp_posts.post_status`.
+    pub fn as_wp(&self) -> &'static str {
+        match self {
+            PostStatus::Publish => "publish",
+            PostStatus::Draft => "draft",
+            PostStatus::Future => "future",
+            PostStatus::Private => "private",
+            PostStatus::Pending => "pending",
+        }
+    }
+
+    pub fn from_wp(status: &str) -> Option<Self> {
+        match status {
+            "publish" => Some(PostStatus::Publish),
+            "draft" => Some(PostStatus::Draft),
+            "future" => Some(PostStatus::Future),
+            "private" => Some(PostStatus::Private),
+            "pending" => Some(PostStatus::Pending),
+            _ => None,
+        }
+    }
+}
+
+/// Critérios para escolher os posts e páginas lidos da origem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub statuses: Vec<PostStatus>,
+    /// Só posts com imagem no conteúdo (`<img`).
+    pub require_image: bool,
+    /// Só posts com pelo menos uma categoria.
+    pub require_category: bool,
+    /// Só estes ids de post (vazio aceita todos).
+    pub ids: Vec<u64>,
+    /// Publicados a partir deste dia (`post_date`).
+    pub since: Option<NaiveDate>,
+    /// Publicados até este dia, inclusive.
+    pub until: Option<NaiveDate>,
+    /// Só posts deste autor (id do WordPress).
+    pub author: Option<u64>,
+    /// Só posts desta categoria (slug).
+    pub category: Option<String>,
+    /// Só itens alterados a partir deste momento (`post_modified`), usado pelo sync.
+    pub modified_since: Option<String>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            statuses: vec![PostStatus::Publish],
+            require_image: false,
+            require_category: false,
+            ids: vec![],
+            since: None,
+            until: None,
+            author: None,
+            category: None,
+            modified_since: None,
+        }
+    }
+}
+
+impl Filter {
+    pub fn accepts_status(&self, status: &str) -> bool {
+        PostStatus::from_wp(status).is_some_and(|status| self.statuses.contains(&status))
+    }
+
+    pub fn accepts_id(&self, id: u64) -> bool {
+        self.ids.is_empty() || self.ids.contains(&id)
+    }
+
+    /// Início do período, no formato de `post_date` (inclusive).
+    pub fn since_bound(&self) -> Option<String> {
+        self.since.map(|day| format!("{} 00:00:00", day))
+    }
+
+    /// Fim do período, no formato de `post_date` (exclusivo, dia seguinte a `until`).
+    pub fn until_bound(&self) -> Option<String> {
+        self.until
+            .and_then(|day| day.succ_opt())
+            .map(|day| format!("{} 00:00:00", day))
+    }
+
+    pub fn accepts_modified(&self, post_modified: &str) -> bool {
+        self.modified_since
+            .as_deref()
+            .is_none_or(|since| post_modified >= since)
+    }
+
+    /// Compara `post_date` (`YYYY-MM-DD HH:MM:SS`) com `since`/`until`.
+    pub fn accepts_date(&self, post_date: &str) -> bool {
+        let after_since = self
+            .since_bound()
+            .is_none_or(|since| post_date >= since.as_str());
+        let before_until = self
+            .until_bound()
+            .is_none_or(|until| post_date < until.as_str());
+        after_since && before_until
+    }
+}
@@ -0,0 +1,163 @@
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+
+/// Versão do formato de importação do Ghost gerado.
+const GHOST_VERSION: &str = "5.0.0";
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GhostPost {
+    pub id: String,
+    pub title: String,
+    pub slug: String,
+    pub html: String,
+    pub custom_excerpt: Option<String>,
+    pub feature_image: Option<String>,
+    #[serde(rename = "type")]
+    pub post_type: String,
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub published_at: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GhostTag {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GhostUser {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+    pub email: String,
+    pub profile_image: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GhostRole {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GhostRoleUser {
+    pub role_id: String,
+    pub user_id: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GhostPostTag {
+    pub post_id: String,
+    pub tag_id: String,
+    pub sort_order: usize,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GhostPostAuthor {
+    pub post_id: String,
+    pub author_id: String,
+    pub sort_order: usize,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct GhostData {
+    pub posts: Vec<GhostPost>,
+    pub tags: Vec<GhostTag>,
+    pub users: Vec<GhostUser>,
+    pub roles: Vec<GhostRole>,
+    pub roles_users: Vec<GhostRoleUser>,
+    pub posts_tags: Vec<GhostPostTag>,
+    pub posts_authors: Vec<GhostPostAuthor>,
+}
+
+#[derive(Serialize, Debug)]
+struct GhostMeta {
+    exported_on: i64,
+    version: String,
+}
+
+#[derive(Serialize, Debug)]
+struct GhostDb {
+    meta: GhostMeta,
+    data: GhostData,
+}
+
+/// Arquivo de importação do Ghost (`db[0].data`), para o importador do admin.
+#[derive(Serialize, Debug)]
+pub struct GhostImport {
+    db: Vec<GhostDb>,
+}
+
+impl GhostData {
+    /// Adiciona um usuário com seu papel; cada papel aparece uma vez em `roles`.
+    pub fn add_user(&mut self, user: GhostUser, role: &str) {
+        let role_id = role.to_lowercase();
+        if !self.roles.iter().any(|known| known.id == role_id) {
+            self.roles.push(GhostRole {
+                id: role_id.clone(),
+                name: role.to_string(),
+            });
+        }
+        self.roles_users.push(GhostRoleUser {
+            role_id,
+            user_id: user.id.clone(),
+        });
+        self.users.push(user);
+    }
+
+    /// Adiciona um post com seu autor e suas tags, na ordem informada.
+    pub fn add_post(&mut self, post: GhostPost, author_id: Option<String>, tag_ids: Vec<String>) {
+        if let Some(author_id) = author_id {
+            self.posts_authors.push(GhostPostAuthor {
+                post_id: post.id.clone(),
+                author_id,
+                sort_order: 0,
+            });
+        }
+        for (sort_order, tag_id) in tag_ids.into_iter().enumerate() {
+            self.posts_tags.push(GhostPostTag {
+                post_id: post.id.clone(),
+                tag_id,
+                sort_order,
+            });
+        }
+        self.posts.push(post);
+    }
+
+    pub fn into_import(self) -> GhostImport {
+        GhostImport {
+            db: vec![GhostDb {
+                meta: GhostMeta {
+                    exported_on: Utc::now().timestamp_millis(),
+                    version: String::from(GHOST_VERSION),
+                },
+                data: self,
+            }],
+        }
+    }
+}
+
+/// Papel do Ghost equivalente ao do WordPress. Quem não tem papel conhecido
+/// (ou vem do WXR, que não traz papéis) entra como `Author`, já que tem posts.
+pub fn ghost_role(wp_role: Option<&str>) -> &'static str {
+    match wp_role {
+        Some("administrator") => "Administrator",
+        Some("editor") => "Editor",
+        Some("contributor") => "Contributor",
+        _ => "Author",
+    }
+}
+
+/// Converte a data do MySQL (`2021-05-03 10:00:00`) para o ISO 8601 usado pelo Ghost.
+/// Valores fora desse formato são devolvidos como estão.
+pub fn to_ghost_date(date: &str) -> String {
+    match NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S") {
+        Ok(date) => date.and_utc().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+        Err(_) => date.to_string(),
+    }
+}
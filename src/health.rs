@@ -1,12 +1,9 @@
-use ammonia::clean;
 use dotenv::dotenv;
 use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION};
-use reqwest::Client;
-use reqwest::tls::Version;
-use serde::{Deserialize, Serialize};
+use reqwest_middleware::ClientWithMiddleware;
 use std::env;
 use tracing::{error, info};
-use mysql::{prelude::*, Pool, Opts};
+use mysql::{Pool, Opts};
 
 
 pub async fn test_db_connection() -> Result<(), Box<dyn std::error::Error>> {
@@ -23,7 +20,7 @@ pub async fn test_db_connection() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let api_url = match env::var("API_URL") {
+    let _api_url = match env::var("API_URL") {
         Ok(url) => url,
         Err(_) => {
             error!("Variável de ambiente API_URL não encontrada.");
@@ -31,7 +28,7 @@ pub async fn test_db_connection() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let api_token = match env::var("API_TOKEN") {
+    let _api_token = match env::var("API_TOKEN") {
         Ok(url) => url,
         Err(_) => {
             error!("Variável de ambiente API_TOKEN não encontrada.");
@@ -70,13 +67,8 @@ pub async fn test_db_connection() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
-pub async fn heathcheck() {
+pub async fn heathcheck(client: ClientWithMiddleware) {
     dotenv().ok();
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .min_tls_version(Version::TLS_1_2)
-        .build()
-        .unwrap();
     let token = env::var("API_TOKEN").unwrap();
     let api_url = env::var("API_URL").unwrap();
     let url_req = format!("{}/healthcheck", &api_url);
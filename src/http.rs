@@ -0,0 +1,45 @@
+use anyhow::{Context, Result};
+use reqwest::tls::Version;
+use reqwest::Client;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::policies::ExponentialBackoff;
+use reqwest_retry::RetryTransientMiddleware;
+use std::time::Duration;
+
+/// Política de retentativa das requisições para a API.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub min_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            min_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Cria o cliente HTTP compartilhado por todas as migrações.
+///
+/// Falhas transitórias (5xx, 408, 429 e erros de conexão) são repetidas com
+/// backoff exponencial; os demais 4xx voltam na hora, já que repetir um erro
+/// de validação não muda a resposta.
+pub fn build_client(retry: &RetryConfig) -> Result<ClientWithMiddleware> {
+    let client = Client::builder()
+        .min_tls_version(Version::TLS_1_2)
+        .danger_accept_invalid_certs(true)
+        .build()
+        .context("Failed to build http client")?;
+    let retry_policy = ExponentialBackoff::builder()
+        .retry_bounds(retry.min_backoff, retry.max_backoff)
+        .build_with_max_retries(retry.max_retries);
+
+    Ok(ClientBuilder::new(client)
+        .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+        .build())
+}
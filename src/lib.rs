@@ -1,15 +1,80 @@
+pub mod api;
+pub mod authors;
+pub mod dry_run;
+pub mod filter;
+pub mod ghost;
+pub mod http;
+pub mod links;
+pub mod mapping;
+pub mod media;
+pub mod migrate;
+pub mod mysql_source;
+pub mod pages;
+pub mod posts;
+pub mod prune;
+pub mod redirects;
+pub mod sync;
+pub mod tags;
+pub mod throttle;
+pub mod wxr;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use authors::{AuthorPost, ProfileImage};
+use filter::Filter;
 use lazy_static::lazy_static;
+use mapping::Entity;
+use media::MediaFile;
+use pages::PageData;
+use posts::PostData;
+use redirects::Permalinks;
 use rand::Rng;
 use regex::Regex;
+use std::collections::HashMap;
+use tags::TagData;
+
+/// Origem do conteúdo do WordPress (banco MySQL, arquivo WXR, ...).
+#[async_trait]
+pub trait Source: Send + Sync {
+    /// Autores com pelo menos um post aceito pelo filtro.
+    async fn authors(&self, filter: &Filter) -> Result<Vec<AuthorPost>>;
+    async fn tags(&self) -> Result<Vec<TagData>>;
+    async fn posts(&self, filter: &Filter) -> Result<Vec<PostData>>;
+    async fn pages(&self, filter: &Filter) -> Result<Vec<PageData>>;
+    /// `post_status` atual dos ids que ainda existem; os removidos ficam de fora.
+    async fn statuses(&self, ids: &[u64]) -> Result<HashMap<u64, String>>;
+    /// Opções de permalink (`permalink_structure`, `category_base`, `tag_base`), quando a origem as conhece.
+    async fn permalinks(&self) -> Result<Option<Permalinks>>;
+}
+
+/// Destino dos itens já transformados (API, arquivos de dry-run, ...).
+///
+/// Cada envio devolve o id remoto criado, ou `None` quando o destino não
+/// cria nada (como no dry-run).
+#[async_trait]
+pub trait Sink: Send + Sync {
+    async fn send_author(&self, author: &AuthorPost) -> Result<Option<String>>;
+    async fn send_tag(&self, tag: &TagData) -> Result<Option<String>>;
+    async fn send_post(&self, post: &PostData) -> Result<Option<String>>;
+    async fn send_page(&self, page: &PageData) -> Result<Option<String>>;
+    /// Atualiza um post já criado (`remote_id`) em vez de criar outro.
+    async fn update_post(&self, remote_id: &str, post: &PostData) -> Result<Option<String>>;
+    async fn update_page(&self, remote_id: &str, page: &PageData) -> Result<Option<String>>;
+    /// Remove um post ou página do destino.
+    async fn delete(&self, entity: Entity, remote_id: &str) -> Result<Option<String>>;
+    /// Tira um post ou página do ar, voltando para rascunho.
+    async fn unpublish(&self, entity: Entity, remote_id: &str) -> Result<Option<String>>;
+    /// Envia a foto de perfil de um autor já criado.
+    async fn send_profile_image(&self, image: &ProfileImage) -> Result<Option<String>>;
+    /// Envia uma imagem ou PDF e devolve a URL pública no destino.
+    async fn upload_media(&self, media: &MediaFile) -> Result<Option<String>>;
+}
 
 pub fn process_image_url(image_url: &str) -> String {
     if let Some(pos) = image_url.find("/wp-content/") {
         // Concatenate the base URL with everything after "/wp-content/"
         let new_format = format!("/{}", &image_url[pos + 4..]);
-        format!(
-            "{}",
-            new_format.replace("/content/uploads", "/content/images")
-        )
+        new_format.replace("/content/uploads", "/content/images")
     } else {
         // YYYY/MM/name.file
         format!("/content/images/{}", image_url)
@@ -72,9 +137,8 @@ pub fn text_to_html_paragraphs(text: &str) -> String {
     // Processa o texto restante após o último bloco especial
     let remaining = &text[last_end..];
     if !remaining.trim().is_empty() {
-        let (processed, count) = wrap_and_count_paragraphs(remaining);
+        let (processed, _) = wrap_and_count_paragraphs(remaining);
         result.push_str(&processed);
-        p_count += count;
     }
 
 
@@ -113,8 +177,8 @@ fn wrap_and_count_paragraphs(text: &str) -> (String, usize) {
 }
 
 fn insert_advertisement(result: &mut String, p_count: &mut usize) {
-    let mut rng = rand::thread_rng();
-    let random_number = rng.gen_range(1..=3);
+    let mut rng = rand::rng();
+    let random_number = rng.random_range(1..=3);
     
     let ad = format!(
         r#"<a href="/nos-apoie" class="post-anuncio"><img src="/content/images/2025/05/contribua_v{}.jpg" alt="contribua com o opinião socialista" loading="lazy"></a>"#,
@@ -0,0 +1,203 @@
+use crate::filter::Filter;
+use crate::posts::HtmlOptions;
+use crate::Source;
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
+use url::Url;
+
+lazy_static! {
+    static ref HREF: Regex = Regex::new(r#"href="([^"]+)""#).unwrap();
+}
+
+// Base para interpretar links relativos (`/slug/`, `?p=12`)
+const RELATIVE_BASE: &str = "http://relative.invalid";
+
+// Caminhos do WordPress que não são posts nem páginas
+const NOT_CONTENT: [&str; 5] = ["wp-content", "wp-admin", "wp-includes", "wp-json", "feed"];
+
+// Arquivos que não devem ser resolvidos pelo último trecho do caminho
+const ARCHIVES: [&str; 3] = ["category", "tag", "author"];
+
+/// Como um link do conteúdo foi tratado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    /// Outro site, âncora, mídia ou a home: fica como está.
+    Keep,
+    /// Post ou página migrado, com o novo endereço.
+    Resolved(String),
+    /// Aponta para o WordPress mas não corresponde a nenhum post ou página.
+    Unresolved,
+}
+
+/// Reescreve links entre posts e páginas para o formato de URL do destino.
+///
+/// Reconhece os links por `?p=ID`/`?page_id=ID`, pelo `guid` ou pelo slug
+/// (último trecho do caminho). O formato usa `{slug}`, ex: `/{slug}/`.
+#[derive(Debug, Default)]
+pub struct LinkRewriter {
+    format: String,
+    hosts: HashSet<String>,
+    by_id: HashMap<u64, String>,
+    by_guid: HashMap<String, String>,
+    slugs: HashSet<String>,
+    unresolved: Mutex<BTreeSet<(u64, String)>>,
+}
+
+impl LinkRewriter {
+    pub fn new(format: &str) -> Self {
+        Self {
+            format: format.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Índice com todos os posts e páginas nos status de `filter`, não só os
+    /// selecionados, para que links para itens de outras execuções também sejam resolvidos.
+    pub async fn from_source(source: &dyn Source, filter: &Filter, format: &str) -> Result<Self> {
+        let everything = Filter {
+            statuses: filter.statuses.clone(),
+            ..Filter::default()
+        };
+        let mut links = Self::new(format);
+        for post in source.posts(&everything).await? {
+            links.add(post.id, &post.slug, &post.guid);
+        }
+        for page in source.pages(&everything).await? {
+            links.add(page.id, &page.slug, "");
+        }
+        info!("link index with {} items and hosts {:?}", links.by_id.len(), links.hosts);
+        Ok(links)
+    }
+
+    /// Registra um post ou página; o domínio do `guid` passa a contar como interno.
+    pub fn add(&mut self, id: u64, slug: &str, guid: &str) {
+        if slug.is_empty() {
+            return;
+        }
+        self.by_id.insert(id, slug.to_string());
+        self.slugs.insert(slug.to_string());
+        if !guid.is_empty() {
+            self.by_guid.insert(guid.to_string(), slug.to_string());
+            if let Some(host) = Url::parse(guid).ok().and_then(|url| url.host_str().map(normalize_host)) {
+                self.hosts.insert(host);
+            }
+        }
+    }
+
+    /// Considera `host` (ex: o domínio antigo do blog, ou a URL dele) como interno.
+    pub fn add_host(&mut self, host: &str) {
+        let host = match Url::parse(host) {
+            Ok(url) => url.host_str().unwrap_or_default().to_string(),
+            Err(_) => host.trim_end_matches('/').to_string(),
+        };
+        if !host.is_empty() {
+            self.hosts.insert(normalize_host(&host));
+        }
+    }
+
+    pub fn resolve(&self, href: &str) -> Link {
+        let href = href.replace("&amp;", "&");
+        if let Some(slug) = self.by_guid.get(&href) {
+            return Link::Resolved(self.permalink(slug, None));
+        }
+        let url = match Url::parse(&href) {
+            Ok(url) if url.host_str().map(normalize_host).is_some_and(|host| self.hosts.contains(&host)) => url,
+            Ok(_) => return Link::Keep,
+            Err(_) if href.starts_with('/') || href.starts_with('?') => {
+                match Url::parse(RELATIVE_BASE).and_then(|base| base.join(&href)) {
+                    Ok(url) => url,
+                    Err(_) => return Link::Keep,
+                }
+            }
+            Err(_) => return Link::Keep,
+        };
+
+        let id = url
+            .query_pairs()
+            .find(|(key, _)| key == "p" || key == "page_id")
+            .and_then(|(_, value)| value.parse::<u64>().ok());
+        if let Some(id) = id {
+            return match self.by_id.get(&id) {
+                Some(slug) => Link::Resolved(self.permalink(slug, url.fragment())),
+                None => Link::Unresolved,
+            };
+        }
+
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|segment| !segment.is_empty()).collect())
+            .unwrap_or_default();
+        match segments.first() {
+            None => return Link::Keep,
+            Some(first) if NOT_CONTENT.contains(first) => return Link::Keep,
+            Some(first) if ARCHIVES.contains(first) => return Link::Unresolved,
+            Some(_) => {}
+        }
+        match segments.last().filter(|slug| self.slugs.contains(**slug)) {
+            Some(slug) => Link::Resolved(self.permalink(slug, url.fragment())),
+            None => Link::Unresolved,
+        }
+    }
+
+    /// Reescreve os `href` do HTML de `item_id`, guardando os que não foram resolvidos.
+    pub fn rewrite_html(&self, item_id: u64, html: &str) -> String {
+        HREF.replace_all(html, |cap: &regex::Captures| match self.resolve(&cap[1]) {
+            Link::Resolved(permalink) => format!(r#"href="{}""#, permalink),
+            Link::Keep => cap[0].to_string(),
+            Link::Unresolved => {
+                self.unresolved.lock().unwrap().insert((item_id, cap[1].to_string()));
+                cap[0].to_string()
+            }
+        })
+        .into_owned()
+    }
+
+    /// Links internos que não correspondem a nenhum post ou página: `(wp_id, href)`.
+    pub fn unresolved(&self) -> Vec<(u64, String)> {
+        self.unresolved.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Lista no stdout os links internos não resolvidos.
+    pub fn report(&self) {
+        let unresolved = self.unresolved();
+        if unresolved.is_empty() {
+            return;
+        }
+        warn!("{} internal links not resolved", unresolved.len());
+        println!("Links internos não resolvidos ({}):", unresolved.len());
+        for (wp_id, href) in unresolved {
+            println!("  {:<8} {}", wp_id, href);
+        }
+    }
+
+    fn permalink(&self, slug: &str, fragment: Option<&str>) -> String {
+        let permalink = self.format.replace("{slug}", slug);
+        match fragment {
+            Some(fragment) => format!("{}#{}", permalink, fragment),
+            None => permalink,
+        }
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_start_matches("www.").to_lowercase()
+}
+
+/// Monta o índice de links quando `--rewrite-links` foi pedido; sem índice os links ficam como estão.
+pub async fn link_rewriter(source: &dyn Source, filter: &Filter, html: &HtmlOptions) -> Option<Arc<LinkRewriter>> {
+    let format = html.link_format.as_deref()?;
+    match LinkRewriter::from_source(source, filter, format).await {
+        Ok(mut links) => {
+            html.link_hosts.iter().for_each(|host| links.add_host(host));
+            Some(Arc::new(links))
+        }
+        Err(e) => {
+            error!("Fail to index posts for link rewriting, keeping links: {:?}", e);
+            None
+        }
+    }
+}
@@ -1,16 +1,145 @@
-use authors::migrate_authors;
 use clap::Parser;
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, ContentArgs, MigrateArgs, PruneArgs, SourceKind, TagMappingArgs};
+use export::export_ghost;
 use health::heathcheck;
-use health::test_db_connection;
-use posts::migrate_posts;
-use tags::migrate_tags;
+use sites::list_sites;
+use uploads::copy_media;
+use killer::authors::migrate_authors;
+use killer::http::build_client;
+use killer::mapping::IdMap;
+use killer::filter::Filter;
+use killer::pages::migrate_pages;
+use killer::posts::migrate_posts;
+use killer::prune::{check_missing, find_prunable, prune, PruneReason};
+use killer::redirects::{build_redirects, write_redirects, RedirectOptions};
+use killer::tags::migrate_tags;
+use killer::sync::{sync, SyncState};
+use killer::{Sink, Source};
+use reqwest_middleware::ClientWithMiddleware;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::error;
 
-mod authors;
 mod cli;
+mod export;
 mod health;
-mod posts;
-mod tags;
+mod sites;
+mod uploads;
+
+fn open_sink(args: &MigrateArgs, client: ClientWithMiddleware) -> Option<Arc<dyn Sink>> {
+    match args.sink(client) {
+        Ok(sink) => Some(sink),
+        Err(e) => {
+            error!("Fail to configure api: {:?}", e);
+            None
+        }
+    }
+}
+
+async fn run_sync(
+    source: &dyn Source,
+    sink: Arc<dyn Sink>,
+    id_map: Arc<IdMap>,
+    content_args: &ContentArgs,
+    tags: &TagMappingArgs,
+    client: &ClientWithMiddleware,
+) {
+    let mut state = match SyncState::from_env() {
+        Ok(state) => state,
+        Err(e) => {
+            error!("Fail to load sync state: {:?}", e);
+            return;
+        }
+    };
+    let (posts, pages) = sync(source, sink, id_map, &mut state, &content_args.options(tags, client)).await;
+    println!(
+        "Sync: {} posts e {} páginas enviados, {} falhas",
+        posts.sent,
+        pages.sent,
+        posts.failed + pages.failed
+    );
+    // No dry-run nada foi enviado, então a marca não avança
+    if content_args.migrate.dry_run {
+        return;
+    }
+    if let Err(e) = state.save() {
+        error!("Fail to save sync state: {:?}", e);
+    }
+}
+
+// Pergunta no terminal; qualquer coisa diferente de "s" cancela
+fn confirm(question: &str) -> bool {
+    print!("{} [s/N] ", question);
+    io::stdout().flush().ok();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "s" | "sim" | "y" | "yes")
+}
+
+async fn run_redirects(source: &dyn Source, filter: &Filter, options: &RedirectOptions, output: &Path) {
+    let result = async {
+        let redirects = build_redirects(source, filter, options).await?;
+        write_redirects(output, &redirects)?;
+        Ok::<_, anyhow::Error>(redirects.len())
+    }
+    .await;
+
+    match result {
+        Ok(count) => println!("{} redirecionamentos em {}", count, output.display()),
+        Err(e) => error!("Fail to build redirects: {:?}", e),
+    }
+}
+
+async fn run_prune(
+    source: &dyn Source,
+    sink: Arc<dyn Sink>,
+    id_map: &IdMap,
+    partial_source: bool,
+    prune_args: &PruneArgs,
+) {
+    let candidates = match find_prunable(source, id_map).await {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            error!("Fail to compare id mapping with source: {:?}", e);
+            return;
+        }
+    };
+    let candidates = match check_missing(candidates, id_map, partial_source, prune_args.allow_missing) {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            error!("{:?}", e);
+            println!("Use --allow-missing se os itens foram mesmo apagados no WordPress");
+            return;
+        }
+    };
+    if candidates.is_empty() {
+        println!("Nada para remover");
+        return;
+    }
+    for candidate in &candidates {
+        let reason = match candidate.reason {
+            PruneReason::Trashed => "lixeira",
+            PruneReason::Missing => "apagado",
+        };
+        println!(
+            "{:<6} wp {:<8} -> {:<26} ({})",
+            candidate.entity.as_str(),
+            candidate.wp_id,
+            candidate.remote_id,
+            reason
+        );
+    }
+    let question = format!("Aplicar {:?} em {} itens?", prune_args.action, candidates.len());
+    if !prune_args.dry_run && !prune_args.yes && !confirm(&question) {
+        println!("Cancelado");
+        return;
+    }
+    let summary = prune(sink.as_ref(), id_map, &candidates, prune_args.action.into()).await;
+    println!("Prune: {} itens, {} falhas", summary.sent, summary.failed);
+}
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
@@ -19,27 +148,119 @@ async fn main() {
         .init();
 
     let args = Cli::parse();
+    dotenv::dotenv().ok();
+    let id_map = match IdMap::from_env() {
+        Ok(id_map) => Arc::new(id_map),
+        Err(e) => {
+            error!("Fail to load id mapping: {:?}", e);
+            return;
+        }
+    };
+    if args.command.uses_mapping() {
+        if let Err(e) = id_map.bind_blog(args.source.blog_id.unwrap_or(1)) {
+            error!("{:?}", e);
+            return;
+        }
+    }
+    let client = match build_client(&args.retry.config()) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Fail to build http client: {:?}", e);
+            return;
+        }
+    };
+    // Sem --referenced-only o `media` só copia o diretório de uploads e não precisa da origem
+    if let Commands::Media {
+        uploads_dir,
+        output,
+        manifest,
+        referenced_only,
+        filter,
+    } = &args.command
+    {
+        let source = if *referenced_only {
+            args.source.test_connection().await;
+            match args.source.open() {
+                Ok(source) => Some(source),
+                Err(e) => {
+                    error!("Fail to load source: {:?}", e);
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        copy_media(source.as_deref(), &filter.filter(), uploads_dir, output, manifest).await;
+        return;
+    }
+    let source = match args.source.open() {
+        Ok(source) => source,
+        Err(e) => {
+            error!("Fail to load source: {:?}", e);
+            return;
+        }
+    };
     match args.command {
-        Commands::Authors => {
-            let _ = test_db_connection().await;
-            migrate_authors().await;
+        Commands::Authors(author_args) => {
+            args.source.test_connection().await;
+            if let Some(sink) = open_sink(&author_args.migrate, client.clone()) {
+                migrate_authors(source.as_ref(), sink, id_map, &author_args.options(&args.tags, &client)).await;
+            }
         }
         Commands::Test => {
-            let _ = test_db_connection().await;
-            let _ = heathcheck().await;
+            args.source.test_connection().await;
+            heathcheck(client).await;
+        }
+        Commands::Pages(content_args) => {
+            args.source.test_connection().await;
+            if let Some(sink) = open_sink(&content_args.migrate, client.clone()) {
+                migrate_pages(source.as_ref(), sink, id_map, &content_args.options(&args.tags, &client)).await;
+            }
+        }
+        Commands::Posts(content_args) => {
+            args.source.test_connection().await;
+            if let Some(sink) = open_sink(&content_args.migrate, client.clone()) {
+                migrate_posts(source.as_ref(), sink, id_map, &content_args.options(&args.tags, &client)).await;
+            }
+        }
+        Commands::Tags(migrate_args) => {
+            args.source.test_connection().await;
+            if let Some(sink) = open_sink(&migrate_args, client) {
+                migrate_tags(source.as_ref(), sink, id_map, &migrate_args.options(&args.tags)).await;
+            }
+        }
+        Commands::Sync(content_args) => {
+            args.source.test_connection().await;
+            if let Some(sink) = open_sink(&content_args.migrate, client.clone()) {
+                run_sync(source.as_ref(), sink, id_map, &content_args, &args.tags, &client).await;
+            }
+        }
+        Commands::Sites => {
+            args.source.test_connection().await;
+            list_sites(&args.source).await;
         }
-        Commands::Pages => {
-            let _ = test_db_connection().await;
-            // let _ = send_page().await;
-            //TODO: create migration pages
+        Commands::Export { output, filter, html } => {
+            args.source.test_connection().await;
+            export_ghost(source.as_ref(), &args.tags.mapping(), &filter.filter(), &html.options(), &output).await;
         }
-        Commands::Posts => {
-            let _ = test_db_connection().await;
-            let _ = migrate_posts().await;
+        Commands::Media { .. } => unreachable!("media is handled before opening the source"),
+        Commands::Redirects {
+            output,
+            redirects,
+            filter,
+        } => {
+            args.source.test_connection().await;
+            run_redirects(source.as_ref(), &filter.filter(), &redirects.options(), &output).await;
         }
-        Commands::Tags => {
-            let _ = test_db_connection().await;
-            let _ = migrate_tags().await;
+        Commands::Prune(prune_args) => {
+            args.source.test_connection().await;
+            match prune_args.sink(client) {
+                Ok(sink) => {
+                    let partial_source = matches!(args.source.source, SourceKind::Wxr);
+                    run_prune(source.as_ref(), sink, &id_map, partial_source, &prune_args).await
+                }
+                Err(e) => error!("Fail to configure api: {:?}", e),
+            }
         }
     }
 }
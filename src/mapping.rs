@@ -0,0 +1,238 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Tipo de entidade migrada, usado como chave de primeiro nível no arquivo.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Entity {
+    Authors,
+    Tags,
+    Posts,
+    Pages,
+    /// Foto de perfil já enviada, pelo id do autor no WordPress; o valor é o caminho da imagem
+    #[serde(rename = "profile_images")]
+    ProfileImages,
+}
+
+impl Entity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Entity::Authors => "authors",
+            Entity::Tags => "tags",
+            Entity::Posts => "posts",
+            Entity::Pages => "pages",
+            Entity::ProfileImages => "profile_images",
+        }
+    }
+}
+
+type Entries = BTreeMap<Entity, BTreeMap<String, String>>;
+
+/// Conteúdo do arquivo: o site de origem e os ids de cada entidade.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct MappingFile {
+    /// Site do multisite (`--blog-id`, 1 no site principal) que gerou os ids.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blog_id: Option<u64>,
+    #[serde(flatten)]
+    entries: Entries,
+}
+
+/// Uma alteração do mapeamento, gravada como uma linha do journal.
+#[derive(Serialize, Deserialize, Debug)]
+struct Change {
+    entity: Entity,
+    wp_id: String,
+    /// `None` remove o item.
+    remote_id: Option<String>,
+}
+
+/// Mapeamento persistente `wp_id -> remote_id` por tipo de entidade.
+///
+/// Cada alteração é acrescentada como uma linha JSON em `<arquivo>.log`, então
+/// o conteúdo sobrevive a uma execução interrompida sem regravar o arquivo
+/// inteiro. Ao carregar, o journal é aplicado e compactado no arquivo JSON.
+#[derive(Debug)]
+pub struct IdMap {
+    path: PathBuf,
+    blog_id: Mutex<Option<u64>>,
+    entries: Mutex<Entries>,
+    journal: OnceLock<File>,
+}
+
+impl IdMap {
+    /// Carrega o mapeamento de `path`, ou começa vazio se o arquivo não existir.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file: MappingFile = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read mapping file {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse mapping file {}", path.display()))?
+        } else {
+            MappingFile::default()
+        };
+
+        let journal_path = journal_path(&path);
+        if journal_path.exists() {
+            let content = fs::read_to_string(&journal_path)
+                .with_context(|| format!("Failed to read mapping journal {}", journal_path.display()))?;
+            // Uma última linha pela metade (execução interrompida) é ignorada
+            for change in content.lines().filter_map(|line| serde_json::from_str::<Change>(line).ok()) {
+                apply(&mut file.entries, change);
+            }
+            save(&path, &file)?;
+            fs::remove_file(&journal_path)
+                .with_context(|| format!("Failed to remove mapping journal {}", journal_path.display()))?;
+        }
+
+        Ok(Self {
+            path,
+            blog_id: Mutex::new(file.blog_id),
+            entries: Mutex::new(file.entries),
+            journal: OnceLock::new(),
+        })
+    }
+
+    /// Carrega o arquivo indicado por `MAPPING_FILE` (padrão `mapping.json`).
+    pub fn from_env() -> Result<Self> {
+        let path = env::var("MAPPING_FILE").unwrap_or_else(|_| String::from("mapping.json"));
+        Self::load(path)
+    }
+
+    /// Confere se o arquivo é do site `blog_id`, para que `--resume` e `prune` não
+    /// usem ids de outro site do multisite. Um arquivo sem site passa a ser deste.
+    pub fn bind_blog(&self, blog_id: u64) -> Result<()> {
+        let recorded = *self.blog_id.lock().unwrap();
+        match recorded {
+            Some(recorded) if recorded == blog_id => Ok(()),
+            Some(recorded) => bail!(
+                "{} belongs to blog {}, not {}; use another MAPPING_FILE for this site",
+                self.path.display(),
+                recorded,
+                blog_id
+            ),
+            None => {
+                *self.blog_id.lock().unwrap() = Some(blog_id);
+                self.save()
+            }
+        }
+    }
+
+    // Grava o JSON com o estado atual, fora dos locks
+    fn save(&self) -> Result<()> {
+        let file = MappingFile {
+            blog_id: *self.blog_id.lock().unwrap(),
+            entries: self.entries.lock().unwrap().clone(),
+        };
+        save(&self.path, &file)
+    }
+
+    pub fn get(&self, entity: Entity, wp_id: impl ToString) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(&entity)?.get(&wp_id.to_string()).cloned()
+    }
+
+    pub fn contains(&self, entity: Entity, wp_id: impl ToString) -> bool {
+        self.get(entity, wp_id).is_some()
+    }
+
+    /// Quantidade de itens já migrados de uma entidade.
+    pub fn count(&self, entity: Entity) -> usize {
+        let entries = self.entries.lock().unwrap();
+        entries.get(&entity).map_or(0, |ids| ids.len())
+    }
+
+    /// Remove de `items` os que já têm id remoto, devolvendo quantos foram pulados.
+    pub fn retain_pending<T, K: ToString>(
+        &self,
+        entity: Entity,
+        items: &mut Vec<T>,
+        wp_id: impl Fn(&T) -> K,
+    ) -> usize {
+        let before = items.len();
+        items.retain(|item| !self.contains(entity, wp_id(item)));
+        before - items.len()
+    }
+
+    /// Pares `wp_id -> remote_id` já migrados de uma entidade.
+    pub fn entries(&self, entity: Entity) -> BTreeMap<String, String> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(&entity).cloned().unwrap_or_default()
+    }
+
+    /// Esquece um item (ex: removido do destino) e grava no journal.
+    pub fn remove(&self, entity: Entity, wp_id: impl ToString) -> Result<()> {
+        self.change(Change {
+            entity,
+            wp_id: wp_id.to_string(),
+            remote_id: None,
+        })
+    }
+
+    /// Registra o id remoto e grava no journal.
+    pub fn insert(&self, entity: Entity, wp_id: impl ToString, remote_id: impl ToString) -> Result<()> {
+        self.change(Change {
+            entity,
+            wp_id: wp_id.to_string(),
+            remote_id: Some(remote_id.to_string()),
+        })
+    }
+
+    // Aplica em memória e só depois escreve, sem segurar o lock durante a escrita
+    fn change(&self, change: Change) -> Result<()> {
+        let mut line = serde_json::to_string(&change).context("Failed to serialize mapping change")?;
+        line.push('\n');
+        apply(&mut self.entries.lock().unwrap(), change);
+        // Com O_APPEND cada linha vai inteira para o fim do arquivo
+        let mut journal = self.journal()?;
+        journal
+            .write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write mapping journal {}", journal_path(&self.path).display()))
+    }
+
+    fn journal(&self) -> Result<&File> {
+        if let Some(file) = self.journal.get() {
+            return Ok(file);
+        }
+        // O JSON é criado junto com o journal, não em comandos que só leem
+        if !self.path.exists() {
+            self.save()?;
+        }
+        let path = journal_path(&self.path);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open mapping journal {}", path.display()))?;
+        Ok(self.journal.get_or_init(|| file))
+    }
+}
+
+fn journal_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".log");
+    PathBuf::from(name)
+}
+
+fn apply(entries: &mut Entries, change: Change) {
+    let ids = entries.entry(change.entity).or_default();
+    match change.remote_id {
+        Some(remote_id) => ids.insert(change.wp_id, remote_id),
+        None => ids.remove(&change.wp_id),
+    };
+}
+
+fn save(path: &Path, file: &MappingFile) -> Result<()> {
+    let content = serde_json::to_string_pretty(file).context("Failed to serialize mapping")?;
+    // Grava em um arquivo temporário e renomeia, para nunca deixar o JSON pela metade
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, content).with_context(|| format!("Failed to write mapping file {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace mapping file {}", path.display()))
+}
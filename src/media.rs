@@ -0,0 +1,264 @@
+use crate::{process_image_url, Sink};
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use tracing::{error, info};
+
+lazy_static! {
+    // Caminhos já reescritos por `process_image_url`
+    static ref LOCAL_MEDIA: Regex = Regex::new(r#"/content/images/[^"'\s<>)?#]+"#).unwrap();
+    // Arquivos do WordPress citados no conteúdo original
+    static ref UPLOADS: Regex = Regex::new(r#"/wp-content/uploads/([^"'\s<>)?#]+)"#).unwrap();
+}
+
+/// Arquivo enviado para o endpoint de imagens da API.
+#[derive(Debug, Serialize)]
+pub struct MediaFile {
+    /// Caminho relativo a `content/images`, ex: `2024/01/capa.jpg`.
+    pub path: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub base64: String,
+}
+
+impl MediaFile {
+    pub fn new(path: &str, bytes: &[u8]) -> Self {
+        let file_name = path.rsplit('/').next().unwrap_or(path).to_string();
+        Self {
+            path: path.to_string(),
+            content_type: content_type(&file_name).to_string(),
+            file_name,
+            base64: STANDARD.encode(bytes),
+        }
+    }
+}
+
+/// Tipo do arquivo pela extensão, suficiente para o que sai do `wp-content/uploads`.
+pub fn content_type(file_name: &str) -> &'static str {
+    let extension = file_name.rsplit('.').next().unwrap_or_default().to_lowercase();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "avif" => "image/avif",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Baixa as mídias referenciadas (do disco ou do WordPress via HTTP), envia
+/// para o destino e troca os caminhos `/content/images/...` pela URL devolvida.
+///
+/// Cada arquivo é enviado uma vez por execução; falhas mantêm o caminho local.
+#[derive(Debug)]
+pub struct MediaUploader {
+    client: ClientWithMiddleware,
+    /// Diretório `wp-content/uploads` no disco, quando o binário roda no servidor.
+    uploads_dir: Option<PathBuf>,
+    /// Endereço do WordPress, para baixar o que não está no disco.
+    base_url: Option<String>,
+    /// Um envio por caminho; quem chega depois espera o primeiro terminar.
+    uploaded: Mutex<HashMap<String, Arc<OnceCell<Option<String>>>>>,
+}
+
+impl MediaUploader {
+    pub fn new(client: ClientWithMiddleware, uploads_dir: Option<PathBuf>, base_url: Option<String>) -> Self {
+        Self {
+            client,
+            uploads_dir,
+            base_url: base_url.map(|url| url.trim_end_matches('/').to_string()),
+            uploaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Lê o arquivo de `path` (relativo a `content/images`, ou uma URL absoluta).
+    ///
+    /// Caminhos com `..`, raiz ou prefixo são recusados para não ler fora de `uploads_dir`.
+    pub async fn fetch(&self, path: &str) -> Result<Vec<u8>> {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            return self.download(path).await;
+        }
+        let Some(relative) = relative_path(path) else {
+            bail!("{} is not a path inside the uploads dir", path);
+        };
+        if let Some(uploads_dir) = &self.uploads_dir {
+            let file = uploads_dir.join(relative);
+            if file.is_file() {
+                return fs::read(&file).with_context(|| format!("Failed to read {}", file.display()));
+            }
+        }
+        match &self.base_url {
+            Some(base_url) => self.download(&format!("{}/wp-content/uploads/{}", base_url, path)).await,
+            None => bail!("{} not found in uploads dir and no base url", path),
+        }
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            bail!("download {} failed: {}", url, response.status());
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    // URL remota de um caminho local, enviando o arquivo na primeira vez
+    async fn upload(&self, sink: &dyn Sink, local: &str) -> Option<String> {
+        let cell = self.uploaded.lock().unwrap().entry(local.to_string()).or_default().clone();
+        cell.get_or_init(|| self.send_file(sink, local)).await.clone()
+    }
+
+    async fn send_file(&self, sink: &dyn Sink, local: &str) -> Option<String> {
+        let path = local.trim_start_matches("/content/images/");
+        let remote = match self.fetch(path).await {
+            Ok(bytes) => match sink.upload_media(&MediaFile::new(path, &bytes)).await {
+                Ok(remote) => remote,
+                Err(e) => {
+                    error!("Fail to upload media {}: {:?}", path, e);
+                    None
+                }
+            },
+            Err(e) => {
+                error!("Fail to fetch media {}: {:?}", path, e);
+                None
+            }
+        };
+        if let Some(url) = &remote {
+            info!("media {} uploaded as {}", path, url);
+        }
+        remote
+    }
+
+    /// Envia as mídias do HTML e devolve o HTML com as URLs do destino.
+    pub async fn rewrite_html(&self, sink: &dyn Sink, html: String) -> String {
+        let mut locals: Vec<String> = LOCAL_MEDIA
+            .find_iter(&html)
+            .map(|found| found.as_str().to_string())
+            .collect();
+        locals.sort();
+        locals.dedup();
+        let mut remotes = HashMap::new();
+        for local in locals {
+            if let Some(remote) = self.upload(sink, &local).await {
+                remotes.insert(local, remote);
+            }
+        }
+        // Troca cada ocorrência inteira; um caminho pode ser prefixo de outro (`a.jpg`, `a.jpg.webp`)
+        LOCAL_MEDIA
+            .replace_all(&html, |found: &regex::Captures| {
+                remotes.get(&found[0]).cloned().unwrap_or_else(|| found[0].to_string())
+            })
+            .into_owned()
+    }
+
+    /// Envia a imagem de destaque, se houver, e devolve a URL do destino.
+    pub async fn rewrite_url(&self, sink: &dyn Sink, image_url: Option<String>) -> Option<String> {
+        match image_url {
+            Some(local) if local.starts_with("/content/images/") => {
+                let remote = self.upload(sink, &local).await;
+                Some(remote.unwrap_or(local))
+            }
+            image_url => image_url,
+        }
+    }
+}
+
+// Só aceita trechos normais (`2024/01/capa.jpg`), nunca `..`, `/` ou `C:`
+fn relative_path(path: &str) -> Option<&Path> {
+    let relative = Path::new(path);
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then_some(relative)
+}
+
+/// Arquivos (relativos a `wp-content/uploads`) citados no HTML ou na imagem de destaque.
+pub fn referenced_uploads(html: &str, image_url: Option<&str>) -> Vec<String> {
+    let mut paths: Vec<String> = UPLOADS
+        .captures_iter(html)
+        .map(|cap| cap[1].to_string())
+        .collect();
+    if let Some(image_url) = image_url.filter(|url| !url.is_empty()) {
+        let local = process_image_url(image_url);
+        paths.push(local.trim_start_matches("/content/images/").to_string());
+    }
+    paths
+}
+
+/// Copia `uploads_dir` para `images_dir` mantendo o caminho de cada arquivo
+/// (`YYYY/MM/nome.jpg`), como `process_image_url` espera.
+///
+/// Com `referenced`, só copia os arquivos da lista. Arquivos já copiados com o
+/// mesmo tamanho são mantidos. Devolve o manifesto `caminho antigo -> novo`.
+pub fn copy_uploads(
+    uploads_dir: &Path,
+    images_dir: &Path,
+    referenced: Option<&HashSet<String>>,
+) -> Result<BTreeMap<String, String>> {
+    let mut files = vec![];
+    walk(uploads_dir, &mut files)?;
+    let mut manifest = BTreeMap::new();
+    for file in files {
+        let relative = file
+            .strip_prefix(uploads_dir)
+            .with_context(|| format!("{} outside uploads dir", file.display()))?;
+        let path = relative
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if referenced.is_some_and(|referenced| !referenced.contains(&path)) {
+            continue;
+        }
+        let target = images_dir.join(relative);
+        if !same_size(&file, &target) {
+            if let Some(dir) = target.parent() {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+            }
+            fs::copy(&file, &target)
+                .with_context(|| format!("Failed to copy {} to {}", file.display(), target.display()))?;
+        }
+        manifest.insert(
+            format!("/wp-content/uploads/{}", path),
+            format!("/content/images/{}", path),
+        );
+    }
+    info!("copied {} files into {}", manifest.len(), images_dir.display());
+    Ok(manifest)
+}
+
+/// Grava o manifesto de `copy_uploads` em JSON.
+pub fn write_manifest(path: &Path, manifest: &BTreeMap<String, String>) -> Result<()> {
+    let content = serde_json::to_string_pretty(manifest).context("Failed to serialize manifest")?;
+    fs::write(path, content).with_context(|| format!("Failed to write manifest {}", path.display()))
+}
+
+fn same_size(source: &Path, target: &Path) -> bool {
+    match (fs::metadata(source), fs::metadata(target)) {
+        (Ok(source), Ok(target)) => source.len() == target.len(),
+        _ => false,
+    }
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use crate::filter::Filter;
+use crate::mapping::{Entity, IdMap};
+use crate::media::MediaUploader;
+use crate::posts::HtmlOptions;
+use crate::tags::TagMapping;
+use crate::throttle::Throttle;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// Opções comuns a todas as migrações.
+#[derive(Debug, Clone)]
+pub struct MigrateOptions {
+    /// Pula os itens que já estão no mapeamento.
+    pub resume: bool,
+    /// Máximo de itens processados ao mesmo tempo; o limite por requisição fica no `ApiSink`.
+    pub concurrency: usize,
+    /// Papel de categorias e tags do WordPress no destino.
+    pub tag_mapping: TagMapping,
+    /// Quais autores, posts e páginas são lidos da origem.
+    pub filter: Filter,
+    /// Atualiza os itens que já estão no mapeamento em vez de criá-los de novo.
+    pub sync: bool,
+    /// Envia as mídias referenciadas para o destino e usa as URLs devolvidas.
+    pub media: Option<Arc<MediaUploader>>,
+    /// Ajustes do pipeline de HTML.
+    pub html: HtmlOptions,
+}
+
+impl Default for MigrateOptions {
+    fn default() -> Self {
+        Self {
+            resume: false,
+            concurrency: 8,
+            tag_mapping: TagMapping::default(),
+            filter: Filter::default(),
+            sync: false,
+            media: None,
+            html: HtmlOptions::default(),
+        }
+    }
+}
+
+/// Resultado de uma migração de posts ou páginas.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MigrateSummary {
+    pub sent: usize,
+    pub failed: usize,
+    /// Maior `post_modified` entre os itens lidos da origem.
+    pub latest_modified: Option<String>,
+}
+
+impl MigrateSummary {
+    pub fn new(latest_modified: Option<String>) -> Self {
+        Self {
+            latest_modified,
+            ..Self::default()
+        }
+    }
+
+    pub fn add(&mut self, ok: bool) {
+        if ok {
+            self.sent += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+}
+
+impl MigrateOptions {
+    pub fn throttle(&self) -> Throttle {
+        Throttle::new(self.concurrency, None)
+    }
+
+    /// Aplica `resume`, removendo os itens que já estão no mapeamento.
+    pub fn skip_migrated<T, K: ToString>(
+        &self,
+        id_map: &IdMap,
+        entity: Entity,
+        items: &mut Vec<T>,
+        wp_id: impl Fn(&T) -> K,
+    ) {
+        if self.sync {
+            return;
+        }
+        if self.resume {
+            let skipped = id_map.retain_pending(entity, items, wp_id);
+            info!("resume: skipping {} {:?} already migrated", skipped, entity);
+        } else if id_map.count(entity) > 0 {
+            warn!(
+                "{} {:?} already in mapping, use --resume to skip them",
+                id_map.count(entity),
+                entity
+            );
+        }
+    }
+}
+
+/// Registra no mapeamento o id remoto devolvido pelo `Sink`; `false` se o envio falhou.
+pub fn record_reply(id_map: &IdMap, entity: Entity, wp_id: impl ToString, reply: Result<Option<String>>) -> bool {
+    let wp_id = wp_id.to_string();
+    match reply {
+        Ok(Some(remote_id)) => {
+            info!("{:?} {} saved as {}", entity, wp_id, remote_id);
+            if let Err(e) = id_map.insert(entity, &wp_id, &remote_id) {
+                error!("Failed to save {:?} mapping: {:?}", entity, e);
+                return false;
+            }
+            true
+        }
+        Ok(None) => true,
+        Err(e) => {
+            error!("Failed to send {:?} {}: {:?}", entity, wp_id, e);
+            false
+        }
+    }
+}
+
+/// Agrupa os itens por profundidade na hierarquia (raízes primeiro), para que
+/// cada nível seja enviado só depois que os pais já existem na API.
+pub fn group_by_depth<T>(
+    items: Vec<T>,
+    id: impl Fn(&T) -> String,
+    parent_id: impl Fn(&T) -> Option<String>,
+) -> Vec<Vec<T>> {
+    let parents: HashMap<String, Option<String>> = items
+        .iter()
+        .map(|item| (id(item), parent_id(item)))
+        .collect();
+
+    let depth_of = |item: &T| {
+        let mut depth = 0;
+        let mut current = parent_id(item);
+        while let Some(Some(parent)) = current.as_ref().map(|id| parents.get(id)) {
+            depth += 1;
+            // Proteção contra ciclos na origem
+            if depth > parents.len() {
+                break;
+            }
+            current = parent.clone();
+        }
+        depth
+    };
+
+    let mut levels: Vec<Vec<T>> = vec![];
+    for item in items {
+        let depth = depth_of(&item);
+        if levels.len() <= depth {
+            levels.resize_with(depth + 1, Vec::new);
+        }
+        levels[depth].push(item);
+    }
+    levels
+}
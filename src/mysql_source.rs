@@ -0,0 +1,570 @@
+use crate::authors::AuthorPost;
+use crate::filter::{Filter, PostStatus};
+use crate::pages::PageData;
+use crate::posts::PostData;
+use crate::redirects::Permalinks;
+use crate::tags::{TagData, Taxonomy};
+use crate::Source;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use mysql::{prelude::*, Opts, Params, Pool, PooledConn, Value};
+use regex::Regex;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use tracing::{error, info, warn};
+
+lazy_static! {
+    // Papel ativo no array serializado pelo PHP: `s:6:"editor";b:1;`
+    static ref CAPABILITY: Regex = Regex::new(r#"s:\d+:"([^"]+)";b:1;"#).unwrap();
+}
+
+/// Papéis padrão do WordPress, do maior para o menor.
+const ROLES: [&str; 5] = ["administrator", "editor", "author", "contributor", "subscriber"];
+
+/// Prefixo padrão das tabelas do WordPress.
+pub const DEFAULT_TABLE_PREFIX: &str = "wp_";
+
+/// Lê o conteúdo direto do banco do WordPress.
+pub struct MysqlSource {
+    opts: Opts,
+    pool: Mutex<Option<Pool>>,
+    // `None` até ser configurado ou detectado na primeira consulta
+    prefix: Mutex<Option<String>>,
+    blog_id: Option<u64>,
+}
+
+/// Site de uma instalação multisite, lido de `wp_blogs`.
+#[derive(Debug, Clone)]
+pub struct Site {
+    pub blog_id: u64,
+    pub domain: String,
+    pub path: String,
+    pub public: bool,
+    pub archived: bool,
+    pub deleted: bool,
+}
+
+/// Prefixo das tabelas de um site: o site 1 usa as tabelas principais
+/// (`wp_posts`), os demais `wp_N_posts`.
+pub fn site_prefix(prefix: &str, blog_id: Option<u64>) -> String {
+    match blog_id {
+        Some(blog_id) if blog_id > 1 => format!("{}{}_", prefix, blog_id),
+        _ => prefix.to_string(),
+    }
+}
+
+/// Aceita só letras, números e `_`, já que o prefixo entra direto no SQL.
+pub fn valid_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Descobre o prefixo a partir das tabelas `*_options` do banco.
+///
+/// Em instalações multisite existem `wp_options` e `wp_2_options`, por isso
+/// o nome mais curto vence.
+pub fn detect_prefix<S: AsRef<str>>(tables: &[S]) -> Option<String> {
+    tables
+        .iter()
+        .filter_map(|table| table.as_ref().strip_suffix("options"))
+        .filter(|prefix| prefix.ends_with('_') && valid_prefix(prefix))
+        .min_by_key(|prefix| prefix.len())
+        .map(String::from)
+}
+
+impl MysqlSource {
+    pub fn new(db_url: &str) -> Result<Self> {
+        let opts = Opts::from_url(db_url).context("Failed to parse DB_URL")?;
+        Ok(Self {
+            opts,
+            pool: Mutex::new(None),
+            prefix: Mutex::new(None),
+            blog_id: None,
+        })
+    }
+
+    /// Usa `DB_TABLE_PREFIX` quando definido; senão o prefixo é detectado.
+    pub fn from_env() -> Result<Self> {
+        let db_url = env::var("DB_URL").context("Failed to get DB_URL from env")?;
+        let source = Self::new(&db_url)?;
+        match env::var("DB_TABLE_PREFIX") {
+            Ok(prefix) if !prefix.is_empty() => source.with_prefix(&prefix),
+            _ => Ok(source),
+        }
+    }
+
+    pub fn with_prefix(self, prefix: &str) -> Result<Self> {
+        if !valid_prefix(prefix) {
+            bail!("invalid table prefix {:?}", prefix);
+        }
+        *self.prefix.lock().unwrap() = Some(prefix.to_string());
+        Ok(self)
+    }
+
+    /// Lê os posts, tags e páginas de um site da rede multisite.
+    pub fn with_blog_id(self, blog_id: u64) -> Self {
+        Self {
+            blog_id: Some(blog_id),
+            ..self
+        }
+    }
+
+    /// Lista os sites da rede (`wp_blogs`).
+    pub fn sites(&self) -> Result<Vec<Site>> {
+        let mut conn = self.conn()?;
+        let p = self.prefix(&mut conn)?;
+        let result_query_sites = conn.query_map(
+            format!(
+                "SELECT blog_id, domain, path, public, archived, deleted
+                 FROM {p}blogs
+                 ORDER BY blog_id"
+            ),
+            |(blog_id, domain, path, public, archived, deleted): (u64, String, String, u8, u8, u8)| Site {
+                blog_id,
+                domain,
+                path,
+                public: public == 1,
+                archived: archived == 1,
+                deleted: deleted == 1,
+            },
+        );
+        log_query("sites", result_query_sites)
+    }
+
+    // Usuários ficam sempre nas tabelas compartilhadas; o resto é por site
+    fn tables(&self, conn: &mut PooledConn) -> Result<(String, String)> {
+        let prefix = self.prefix(conn)?;
+        let site = site_prefix(&prefix, self.blog_id);
+        Ok((prefix, site))
+    }
+
+    // Detecta o prefixo uma única vez e guarda para as próximas consultas
+    fn prefix(&self, conn: &mut PooledConn) -> Result<String> {
+        let mut prefix = self.prefix.lock().unwrap();
+        if let Some(prefix) = prefix.as_ref() {
+            return Ok(prefix.clone());
+        }
+        let tables: Vec<String> = conn
+            .query(
+                "SELECT TABLE_NAME FROM information_schema.TABLES
+                 WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME LIKE '%\\_options'",
+            )
+            .context("Failed to list tables from information_schema")?;
+        let detected = match detect_prefix(&tables) {
+            Some(detected) => {
+                info!("detected table prefix {}", detected);
+                detected
+            }
+            None => {
+                warn!(
+                    "no *_options table found, using default prefix {}",
+                    DEFAULT_TABLE_PREFIX
+                );
+                String::from(DEFAULT_TABLE_PREFIX)
+            }
+        };
+        *prefix = Some(detected.clone());
+        Ok(detected)
+    }
+
+    // O pool só é criado na primeira consulta
+    fn conn(&self) -> Result<PooledConn> {
+        let mut pool = self.pool.lock().unwrap();
+        if pool.is_none() {
+            *pool = Some(Pool::new(self.opts.clone()).context("Failed to create connection pool")?);
+        }
+        pool.as_ref()
+            .unwrap()
+            .get_conn()
+            .context("Failed to get connection from pool")
+    }
+}
+
+// Condições do WHERE, com os parâmetros na mesma ordem dos `?`
+#[derive(Debug, Default, Clone)]
+struct Conditions {
+    clauses: Vec<String>,
+    params: Vec<Value>,
+}
+
+impl Conditions {
+    fn push(&mut self, clause: impl Into<String>, params: impl IntoIterator<Item = Value>) {
+        self.clauses.push(clause.into());
+        self.params.extend(params);
+    }
+
+    fn push_in(&mut self, column: &str, values: Vec<Value>) {
+        if values.is_empty() {
+            self.push("1 = 0", []);
+            return;
+        }
+        let placeholders = vec!["?"; values.len()].join(", ");
+        self.push(format!("{} IN ({})", column, placeholders), values);
+    }
+
+    fn sql(&self) -> String {
+        if self.clauses.is_empty() {
+            return String::from("1 = 1");
+        }
+        self.clauses.join("\n                AND ")
+    }
+
+    fn params(&self) -> Params {
+        if self.params.is_empty() {
+            Params::Empty
+        } else {
+            Params::Positional(self.params.clone())
+        }
+    }
+}
+
+// Condições sobre `p` (wp_posts) aplicadas a posts, páginas e autores
+/// Papel do usuário a partir de `wp_capabilities` (array serializado pelo PHP).
+/// Com mais de um, fica o maior papel padrão; senão o primeiro de plugin.
+pub fn capabilities_role(capabilities: &str) -> Option<String> {
+    let roles: Vec<&str> = CAPABILITY
+        .captures_iter(capabilities)
+        .filter_map(|captures| captures.get(1))
+        .map(|role| role.as_str())
+        .collect();
+    ROLES
+        .iter()
+        .find(|role| roles.contains(role))
+        .or(roles.first())
+        .map(|role| role.to_string())
+}
+
+fn post_conditions(filter: &Filter, post_type: &str, p: &str) -> Conditions {
+    let mut conditions = Conditions::default();
+    conditions.push("p.post_type = ?", [Value::from(post_type)]);
+    conditions.push_in(
+        "p.post_status",
+        filter.statuses.iter().map(|status| Value::from(status.as_wp())).collect(),
+    );
+    if !filter.ids.is_empty() {
+        conditions.push_in("p.ID", filter.ids.iter().map(|id| Value::from(*id)).collect());
+    }
+    if let Some(since) = filter.since_bound() {
+        conditions.push("p.post_date >= ?", [Value::from(since)]);
+    }
+    if let Some(until) = filter.until_bound() {
+        conditions.push("p.post_date < ?", [Value::from(until)]);
+    }
+    if let Some(modified_since) = &filter.modified_since {
+        conditions.push("p.post_modified >= ?", [Value::from(modified_since.as_str())]);
+    }
+    if let Some(author) = filter.author {
+        conditions.push("p.post_author = ?", [Value::from(author)]);
+    }
+    if post_type == "post" {
+        if let Some(category) = &filter.category {
+            conditions.push(
+                format!(
+                    "EXISTS (SELECT 1 FROM {p}term_relationships tr
+                    JOIN {p}term_taxonomy tt ON tr.term_taxonomy_id = tt.term_taxonomy_id
+                    JOIN {p}terms t ON tt.term_id = t.term_id
+                    WHERE tr.object_id = p.ID AND tt.taxonomy = 'category' AND t.slug = ?)"
+                ),
+                [Value::from(category.as_str())],
+            );
+        }
+        if filter.require_image {
+            conditions.push("p.post_content LIKE '%<img%'", []);
+        }
+        if filter.require_category {
+            conditions.push(
+                format!(
+                    "EXISTS (SELECT 1 FROM {p}term_relationships tr
+                    JOIN {p}term_taxonomy tt ON tr.term_taxonomy_id = tt.term_taxonomy_id
+                    WHERE tr.object_id = p.ID AND tt.taxonomy = 'category')"
+                ),
+                [],
+            );
+        }
+    }
+    conditions
+}
+
+fn log_query<T>(entity: &str, result: mysql::Result<T>) -> Result<T> {
+    match result {
+        Ok(value) => {
+            info!("ok query {}", entity);
+            Ok(value)
+        }
+        Err(message) => {
+            error!("Fail to query {}: {}", entity, message);
+            Err(message).with_context(|| format!("fail to query {}", entity))
+        }
+    }
+}
+
+#[async_trait]
+impl Source for MysqlSource {
+    async fn authors(&self, filter: &Filter) -> Result<Vec<AuthorPost>> {
+        let mut conn = self.conn()?;
+        let (u, p) = self.tables(&mut conn)?;
+        let conditions = post_conditions(filter, "post", &p);
+        let result_query_authors = conn
+            .exec_map(
+                format!("SELECT DISTINCT
+                    u.ID AS id,
+                    u.display_name AS name,
+                    u.user_email AS email,
+                    u.user_login AS login,
+                    u.user_nicename AS nicename,
+                    u.user_pass AS password,
+                    CAST(u.user_registered AS CHAR) AS created_at,
+                    (SELECT meta_value FROM {u}usermeta WHERE user_id = u.ID AND meta_key = 'molongui_author_image_url') AS profile_image_url,
+                    (SELECT meta_value FROM {u}usermeta WHERE user_id = u.ID AND meta_key = '{p}capabilities') AS capabilities
+                FROM
+                    {u}users u
+                JOIN
+                    {p}posts p ON u.ID = p.post_author
+                LEFT JOIN
+                    {u}usermeta um ON u.ID = um.user_id
+                WHERE
+                    {conditions} AND
+                    u.user_email IS NOT NULL AND
+                    u.user_email <> ''
+                ", conditions = conditions.sql()),
+                conditions.params(),
+                |(id, name, email, login, nicename, password, created_at, profile_image_url, capabilities): (i32, String, String, String, String, String, String, Option<String>, Option<String>)|
+                AuthorPost {
+                    id,
+                    name,
+                    email,
+                    login,
+                    nicename,
+                    role: capabilities.as_deref().and_then(capabilities_role),
+                    password,
+                    created_at,
+                    image_url: profile_image_url,
+                },
+            );
+        log_query("authors", result_query_authors)
+    }
+
+    async fn tags(&self) -> Result<Vec<TagData>> {
+        let mut conn = self.conn()?;
+        let (_, p) = self.tables(&mut conn)?;
+        let result_query_tags = conn.query_map(
+            format!("SELECT
+                 t.term_id AS id,
+                 t.name AS name,
+                 t.slug AS slug,
+                 tt.description AS description,
+                 tt.count AS count,
+                 NULLIF(tt.parent, 0) AS parent_id,
+                 tt.taxonomy AS taxonomy
+             FROM
+                 {p}terms t
+             JOIN
+                 {p}term_taxonomy tt ON t.term_id = tt.term_id
+             WHERE
+                 tt.taxonomy IN ('category', 'post_tag')"),
+            |(id, name, slug, description, count, parent_id, taxonomy): (i32, String, String, String, u64, Option<String>, String)| TagData {
+                id,
+                name,
+                slug,
+                description,
+                count,
+                parent_id,
+                taxonomy: Taxonomy::from_wp(&taxonomy).unwrap_or_default(),
+            },
+        );
+        log_query("tags", result_query_tags)
+    }
+
+    async fn posts(&self, filter: &Filter) -> Result<Vec<PostData>> {
+        let mut conn = self.conn()?;
+        let (_, p) = self.tables(&mut conn)?;
+        let conditions = post_conditions(filter, "post", &p);
+        let result_query_posts = conn.exec_map(
+            format!(r#"
+            SELECT
+        p.ID AS id,
+        p.post_title AS title,
+        p.post_name AS slug,
+        p.post_content AS html,
+        p.post_excerpt AS excerpt,
+        CAST(p.post_date AS CHAR) AS created_at,
+        CAST(p.post_modified AS CHAR) AS updated_at,
+        p.post_status AS status,
+        CAST(p.post_author AS CHAR) AS author_id,
+        MAX(CASE WHEN pm.meta_key = '_thumbnail_id' THEN img_meta.meta_value END) AS image_url,
+        p.guid AS guid
+            FROM
+                {p}posts p
+            LEFT JOIN
+                {p}postmeta pm ON p.ID = pm.post_id AND pm.meta_key = '_thumbnail_id'
+            LEFT JOIN
+                {p}posts img ON img.ID = pm.meta_value
+            LEFT JOIN
+                {p}postmeta img_meta ON img.ID = img_meta.post_id AND img_meta.meta_key = '_wp_attached_file'
+            WHERE
+                {conditions}
+            GROUP BY
+                p.ID;"#, conditions = conditions.sql()),
+            conditions.params(),
+            |(id, title, slug, html, excerpt, created_at, updated_at, status, author_id, image_url, guid): (u64, String, String, String, String, String, String, String, String, Option<String>, String)| PostData {
+                id,
+                title,
+                slug,
+                html,
+                excerpt,
+                created_at,
+                updated_at,
+                status: PostStatus::from_wp(&status).unwrap_or_default(),
+                author_id,
+                image_url,
+                tags: vec![],
+                terms: vec![],
+                guid,
+            },
+        );
+        let mut posts = log_query("posts", result_query_posts)?;
+
+        // Categorias e tags vêm numa consulta separada para manter a ordem de cada post
+        let result_query_terms = conn.exec_map(
+            format!(r#"
+            SELECT
+                tr.object_id AS post_id,
+                t.term_id AS id,
+                t.name AS name,
+                t.slug AS slug,
+                tt.description AS description,
+                tt.count AS count,
+                CAST(NULLIF(tt.parent, 0) AS CHAR) AS parent_id,
+                tt.taxonomy AS taxonomy
+            FROM
+                {p}term_relationships tr
+            INNER JOIN
+                {p}term_taxonomy tt ON tr.term_taxonomy_id = tt.term_taxonomy_id
+            INNER JOIN
+                {p}terms t ON tt.term_id = t.term_id
+            INNER JOIN
+                {p}posts p ON p.ID = tr.object_id
+            WHERE
+                {conditions}
+                AND tt.taxonomy IN ('category', 'post_tag')
+            ORDER BY
+                tr.object_id, tr.term_order, t.name;"#, conditions = conditions.sql()),
+            conditions.params(),
+            |(post_id, id, name, slug, description, count, parent_id, taxonomy): (u64, i32, String, String, String, u64, Option<String>, String)| {
+                (
+                    post_id,
+                    TagData {
+                        id,
+                        name,
+                        slug,
+                        description,
+                        count,
+                        parent_id,
+                        taxonomy: Taxonomy::from_wp(&taxonomy).unwrap_or_default(),
+                    },
+                )
+            },
+        );
+        let mut terms: HashMap<u64, Vec<TagData>> = HashMap::new();
+        for (post_id, term) in log_query("post terms", result_query_terms)? {
+            terms.entry(post_id).or_default().push(term);
+        }
+        for post in posts.iter_mut() {
+            post.terms = terms.remove(&post.id).unwrap_or_default();
+        }
+        Ok(posts)
+    }
+
+    async fn pages(&self, filter: &Filter) -> Result<Vec<PageData>> {
+        let mut conn = self.conn()?;
+        let (_, p) = self.tables(&mut conn)?;
+        let conditions = post_conditions(filter, "page", &p);
+        let result_query_pages = conn.exec_map(
+            format!(r#"
+            SELECT
+        p.ID AS id,
+        p.post_title AS title,
+        p.post_name AS slug,
+        p.post_content AS html,
+        p.post_excerpt AS excerpt,
+        CAST(p.post_date AS CHAR) AS created_at,
+        CAST(p.post_modified AS CHAR) AS updated_at,
+        p.post_status AS status,
+        CAST(p.post_author AS CHAR) AS author_id,
+        MAX(img_meta.meta_value) AS image_url,
+        CAST(NULLIF(p.post_parent, 0) AS CHAR) AS parent_id,
+        p.menu_order AS menu_order
+            FROM
+                {p}posts p
+            LEFT JOIN
+                {p}postmeta pm ON p.ID = pm.post_id AND pm.meta_key = '_thumbnail_id'
+            LEFT JOIN
+                {p}posts img ON img.ID = pm.meta_value
+            LEFT JOIN
+                {p}postmeta img_meta ON img.ID = img_meta.post_id AND img_meta.meta_key = '_wp_attached_file'
+            WHERE
+                {conditions}
+            GROUP BY
+                p.ID
+            ORDER BY
+                p.post_parent, p.menu_order;"#, conditions = conditions.sql()),
+            conditions.params(),
+            |(id, title, slug, html, excerpt, created_at, updated_at, status, author_id, image_url, parent_id, menu_order): (u64, String, String, String, String, String, String, String, String, Option<String>, Option<String>, i32)| PageData {
+                id,
+                title,
+                slug,
+                html,
+                excerpt,
+                created_at,
+                updated_at,
+                status: PostStatus::from_wp(&status).unwrap_or_default(),
+                author_id,
+                image_url,
+                parent_id,
+                menu_order,
+            },
+        );
+        log_query("pages", result_query_pages)
+    }
+
+    async fn statuses(&self, ids: &[u64]) -> Result<HashMap<u64, String>> {
+        let mut conn = self.conn()?;
+        let (_, p) = self.tables(&mut conn)?;
+        let mut statuses = HashMap::new();
+        // Em lotes, para não passar do limite de parâmetros por consulta
+        for chunk in ids.chunks(500) {
+            let mut conditions = Conditions::default();
+            conditions.push_in("p.ID", chunk.iter().map(|id| Value::from(*id)).collect());
+            let result_query_statuses = conn.exec_map(
+                format!(
+                    "SELECT p.ID, p.post_status FROM {p}posts p WHERE {conditions}",
+                    conditions = conditions.sql()
+                ),
+                conditions.params(),
+                |(id, status): (u64, String)| (id, status),
+            );
+            statuses.extend(log_query("statuses", result_query_statuses)?);
+        }
+        Ok(statuses)
+    }
+
+    async fn permalinks(&self) -> Result<Option<Permalinks>> {
+        let mut conn = self.conn()?;
+        let (_, p) = self.tables(&mut conn)?;
+        let result_query_permalinks = conn.query(format!(
+            "SELECT option_name, option_value FROM {p}options \
+             WHERE option_name IN ('permalink_structure', 'category_base', 'tag_base')"
+        ));
+        let options: HashMap<String, String> = log_query("permalinks", result_query_permalinks)?.into_iter().collect();
+        let option = |name: &str| options.get(name).cloned().unwrap_or_default();
+        Ok(options.get("permalink_structure").map(|structure| Permalinks {
+            structure: structure.clone(),
+            category_base: option("category_base"),
+            tag_base: option("tag_base"),
+        }))
+    }
+}
@@ -0,0 +1,158 @@
+use crate::filter::PostStatus;
+use crate::links::{link_rewriter, LinkRewriter};
+use crate::mapping::{Entity, IdMap};
+use crate::migrate::{group_by_depth, record_reply, MigrateOptions, MigrateSummary};
+use crate::posts::{clean_html, process_html, HtmlOptions};
+use crate::{process_image_url, text_to_html_paragraphs, Sink, Source};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PageData {
+    pub id: u64,
+    pub title: String,
+    pub slug: String,
+    pub html: String,
+    pub excerpt: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub status: PostStatus,
+    /// Vazio quando o autor não foi migrado; fica fora do payload.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub author_id: String,
+    pub image_url: Option<String>,
+    pub parent_id: Option<String>,
+    pub menu_order: i32,
+}
+
+impl PageData {
+    fn sanitize(self, content: String) -> Self {
+        let content = text_to_html_paragraphs(&content);
+        let image_url = if let Some(image_to_process) = self.image_url {
+            process_image_url(&image_to_process)
+        } else {
+            String::from("")
+        };
+
+        Self {
+            image_url: Some(image_url),
+            html: clean_html(&content),
+            ..self
+        }
+    }
+
+    // Troca os ids do autor e da página pai pelos ids já criados na API;
+    // os que não foram migrados ficam de fora
+    fn resolve_ids(self, id_map: &IdMap) -> Self {
+        let author_id = id_map.get(Entity::Authors, &self.author_id).unwrap_or_else(|| {
+            warn!("author {} of page {} not migrated yet, sending without author", self.author_id, self.id);
+            String::new()
+        });
+        let parent_id = self.parent_id.and_then(|parent_id| {
+            let resolved = id_map.get(Entity::Pages, &parent_id);
+            if resolved.is_none() {
+                warn!("parent page {} of page {} not migrated yet, sending at the root", parent_id, self.id);
+            }
+            resolved
+        });
+        Self {
+            author_id,
+            parent_id,
+            ..self
+        }
+    }
+}
+
+/// Aplica o pipeline de HTML e imagens, gerando a página como será enviada.
+pub fn prepare_page(page: PageData, html: &HtmlOptions) -> PageData {
+    let processed_html = process_html(page.html.to_string(), html);
+    page.sanitize(processed_html)
+}
+
+/// Como `prepare_page`, reescrevendo antes os links internos no HTML original.
+pub fn prepare_page_with_links(page: PageData, html: &HtmlOptions, links: Option<&LinkRewriter>) -> PageData {
+    let page = match links {
+        Some(links) => PageData {
+            html: links.rewrite_html(page.id, &page.html),
+            ..page
+        },
+        None => page,
+    };
+    prepare_page(page, html)
+}
+
+async fn process_page(
+    sink: &dyn Sink,
+    page: PageData,
+    id_map: &IdMap,
+    options: &MigrateOptions,
+    links: Option<&LinkRewriter>,
+) -> bool {
+    let wp_id = page.id;
+    let mut page_sanitize = prepare_page_with_links(page, &options.html, links).resolve_ids(id_map);
+    if let Some(media) = &options.media {
+        page_sanitize.html = media.rewrite_html(sink, page_sanitize.html).await;
+        page_sanitize.image_url = media.rewrite_url(sink, page_sanitize.image_url).await;
+    }
+    // No sync, páginas já migradas são atualizadas
+    let remote_id = id_map.get(Entity::Pages, wp_id).filter(|_| options.sync);
+    let reply = match remote_id {
+        Some(remote_id) => sink.update_page(&remote_id, &page_sanitize).await,
+        None => sink.send_page(&page_sanitize).await,
+    };
+    record_reply(id_map, Entity::Pages, wp_id, reply)
+}
+
+pub async fn migrate_pages(
+    source: &dyn Source,
+    sink: Arc<dyn Sink>,
+    id_map: Arc<IdMap>,
+    options: &MigrateOptions,
+) -> MigrateSummary {
+    match source.pages(&options.filter).await {
+        Ok(mut pages) => {
+            info!("found {} pages from source", pages.len());
+            let mut summary = MigrateSummary::new(pages.iter().map(|page| page.updated_at.clone()).max());
+            options.skip_migrated(&id_map, Entity::Pages, &mut pages, |page| page.id);
+            let links = link_rewriter(source, &options.filter, &options.html).await;
+            let throttle = Arc::new(options.throttle());
+            for level in group_by_depth(pages, |page| page.id.to_string(), |page| page.parent_id.clone()) {
+                let mut handles = vec![];
+                for page in level {
+                    let sink = sink.clone();
+                    let id_map = id_map.clone();
+                    let throttle = throttle.clone();
+                    let options = options.clone();
+                    let links = links.clone();
+                    let handle = tokio::spawn(async move {
+                        let _permit = throttle.acquire().await;
+                        process_page(sink.as_ref(), page, &id_map, &options, links.as_deref()).await
+                    });
+                    handles.push(handle);
+                }
+                // Aguarda o nível inteiro antes de enviar os filhos
+                for handle in handles {
+                    match handle.await {
+                        Ok(ok) => summary.add(ok),
+                        Err(err) => {
+                            error!("Fail to send process_migrate_page: {:?}", err);
+                            summary.add(false);
+                        }
+                    }
+                }
+            }
+            if let Some(links) = links {
+                links.report();
+            }
+            summary
+        }
+        Err(message) => {
+            error!("Pages not found: {:?}", message);
+            MigrateSummary {
+                failed: 1,
+                ..MigrateSummary::default()
+            }
+        }
+    }
+}
@@ -1,99 +1,37 @@
-use ammonia::clean;
-use anyhow::{Context, Result};
-use dotenv::dotenv;
-use killer::{process_image_url, text_to_html_paragraphs};
-use mockall::predicate::*;
-use mysql::{prelude::*, Pool};
+use crate::filter::PostStatus;
+use crate::links::{link_rewriter, LinkRewriter};
+use crate::mapping::{Entity, IdMap};
+use crate::migrate::{record_reply, MigrateOptions, MigrateSummary};
+use crate::tags::{category_paths, flatten_terms, Hierarchy, TagData, TagMapping, TagRef};
+use crate::{process_image_url, text_to_html_paragraphs, Sink, Source};
+use lazy_static::lazy_static;
 use regex::Regex;
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION};
-use reqwest::tls::Version;
-use reqwest::Client;
-use serde::{Serialize, Deserialize};
-use std::env;
-use tokio;
-use tracing::{error, info};
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct PostReply {
-    id: String,
-    title: String,
-    slug: String,
-    created_at: String,
-    updated_at: String,
-    author_id: String,
-}
-
-async fn send_post(client: Client, post_data: PostData) -> Option<PostReply> {
-    dotenv().ok();
-    let token = env::var("API_TOKEN").unwrap();
-    let api_url = env::var("API_URL").unwrap();
-    let url_req = format!("{}/posts", &api_url);
-
-    info!("send post: { }", post_data.title);
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
-    );
-    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
-    info!("config request");
-    let v = serde_json::to_string(&post_data).unwrap();
-    info!("send post: {}", v);
-    let res = client
-        .post(url_req)
-        .headers(headers)
-        .json(&post_data)
-        .send()
-        .await;
-
-    match res {
-        Ok(response) if response.status().is_success() => {
-            info!("request send");
-            println!("Post enviado com sucesso: {}", post_data.title);
-            let data_res: PostReply = response.json().await.unwrap();
-            Some(data_res)
-        }
-        Ok(response) if response.status().is_client_error() => {
-            error!("request client: {:?}", &post_data);
-            eprintln!(
-                "Falha ao enviar post: {} - Status: {:?}",
-                post_data.title, response
-            );
-            None
-        }
-        Ok(response) if response.status().is_server_error() => {
-            error!("request server error: {:?}", &post_data);
-            eprintln!(
-                "Falha ao enviar post: {} - Status: {:?}",
-                post_data.title, response
-            );
-            None
-        }
-
-        Ok(_) => {
-            error!("request not mapped error: {:?}", &res);
-            None
-        }
-        Err(e) => {
-            error!("request error: {:?}", &e);
-            eprintln!("Erro ao enviar post: {} - Erro: {:?}", post_data.title, e);
-            None
-        }
-    }
-}
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, warn};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PostData {
     pub id: u64,
-    title: String,
-    slug: String,
-    html: String,
-    excerpt: String,
-    created_at: String,
-    updated_at: String,
-    author_id: String,
-    image_url: Option<String>,
-    tags: Option<String>,
+    pub title: String,
+    pub slug: String,
+    pub html: String,
+    pub excerpt: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub status: PostStatus,
+    /// Vazio quando o autor não foi migrado; fica fora do payload.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub author_id: String,
+    pub image_url: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<TagRef>,
+    /// Categorias e tags do post, na ordem da origem.
+    #[serde(skip)]
+    pub terms: Vec<TagData>,
+    /// `guid` do WordPress, usado para reconhecer links internos.
+    #[serde(skip)]
+    pub guid: String,
 }
 
 impl PostData {
@@ -108,90 +46,97 @@ impl PostData {
 
         Self {
             image_url: Some(image_url),
-            html: clean(&content),
+            html: clean_html(&content),
             ..self
         }
     }
-}
-
-async fn get_posts() -> Result<Vec<PostData>, String> {
-    dotenv().ok();
-    let db_url = env::var("DB_URL")
-        .context("Failed to get DB_URL from env")
-        .unwrap();
-    let connection_opts = mysql::Opts::from_url(&db_url)
-        .context("Failed to parse DB_URL")
-        .unwrap();
-    let pool = Pool::new(connection_opts)
-        .context("Failed to create connection pool")
-        .unwrap();
-    let mut conn = pool
-        .get_conn()
-        .context("Failed to get connection from pool")
-        .unwrap();
 
-    let result_query_posts = conn.query_map(
-        r#"
-            SELECT
-        p.ID AS id,
-        p.post_title AS title,
-        p.post_name AS slug,
-        p.post_content AS html,
-        p.post_excerpt AS excerpt,
-        p.post_date AS created_at,
-        p.post_modified AS updated_at,
-        p.post_author AS author_id,
-        MAX(CASE WHEN pm.meta_key = '_thumbnail_id' THEN img_meta.meta_value END) AS image_url,
-        GROUP_CONCAT(t.name) AS tags
-            FROM
-                wp_posts p
-            LEFT JOIN
-                wp_postmeta pm ON p.ID = pm.post_id AND pm.meta_key = '_thumbnail_id'
-            LEFT JOIN
-                wp_posts img ON img.ID = pm.meta_value
-            LEFT JOIN
-                wp_postmeta img_meta ON img.ID = img_meta.post_id AND img_meta.meta_key = '_wp_attached_file'
-            INNER JOIN
-                wp_term_relationships tr ON p.ID = tr.object_id
-            INNER JOIN
-                wp_term_taxonomy tt ON tr.term_taxonomy_id = tt.term_taxonomy_id
-            INNER JOIN
-                wp_terms t ON tt.term_id = t.term_id
-            WHERE
-                p.post_type = 'post'
-                AND p.post_status = 'publish'
-                AND tt.taxonomy = 'category'
-                AND p.post_content LIKE '%<img%'
-            GROUP BY
-                p.ID;"#,
-        |(id, title, slug, html, excerpt, created_at, updated_at, author_id, image_url, tags)| PostData {
-            id,
-            title,
-            slug,
-            html,
-            excerpt,
-            created_at,
-            updated_at,
+    // Troca os ids do WordPress (autor e tags) pelos ids já criados na API;
+    // autor não migrado fica de fora, como as tags sem id
+    fn resolve_ids(self, id_map: &IdMap) -> Self {
+        let author_id = id_map.get(Entity::Authors, &self.author_id).unwrap_or_else(|| {
+            warn!("author {} of post {} not migrated yet, sending without author", self.author_id, self.id);
+            String::new()
+        });
+        let tags = self.tags.into_iter().map(|tag| tag.resolve(id_map)).collect();
+        Self {
             author_id,
-            image_url,
             tags,
-        },
-    );
-
-    match result_query_posts {
-        Ok(res) => {
-            let posts: Vec<PostData> = res;
-            info!("ok query posts");
-            return Ok(posts);
-        }
-        Err(message) => {
-            error!("Fail to query posts: {}", message);
-            Err(String::from("fail to query posts"))
+            ..self
         }
     }
 }
 
-pub async fn process_html(html: String, client: Client) -> String {
+lazy_static! {
+    static ref SRCSET: Regex = Regex::new(r#"\s(srcset|sizes)="([^"]*)""#).unwrap();
+    // Miniatura gerada pelo WordPress: nome-300x200.jpg
+    static ref SIZE_VARIANT: Regex = Regex::new(r"-\d+x\d+(\.[A-Za-z0-9]+)$").unwrap();
+    static ref SANITIZER: ammonia::Builder<'static> = {
+        let mut builder = ammonia::Builder::default();
+        builder.add_tag_attributes("img", &["srcset", "sizes"]);
+        builder
+    };
+}
+
+/// Ajustes do pipeline de HTML.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HtmlOptions {
+    /// Troca as miniaturas (`-300x200.jpg`) pelo arquivo original e remove o `srcset`.
+    pub collapse_sizes: bool,
+    /// Formato dos links internos reescritos, ex: `/{slug}/`; `None` mantém os links.
+    pub link_format: Option<String>,
+    /// Domínios antigos do blog tratados como internos, além dos vistos nos `guid`.
+    pub link_hosts: Vec<String>,
+}
+
+/// Sanitiza o HTML final, mantendo o `srcset` e o `sizes` das imagens.
+///
+/// Roda depois de `text_to_html_paragraphs`, que precisa da classe `wp-image-N`
+/// para converter o `[caption]`; as classes (`wp-image-N`, `size-*`) saem aqui.
+pub fn clean_html(html: &str) -> String {
+    SANITIZER.clean(html).to_string()
+}
+
+/// Troca `nome-300x200.jpg` por `nome.jpg`.
+pub fn collapse_size_variant(url: &str) -> String {
+    SIZE_VARIANT.replace(url, "$1").into_owned()
+}
+
+// Reescreve cada URL do srcset, ou remove srcset/sizes quando as miniaturas viram o original
+fn process_srcset(html: &str, options: &HtmlOptions) -> String {
+    SRCSET
+        .replace_all(html, |cap: &regex::Captures| {
+            if options.collapse_sizes {
+                return String::new();
+            }
+            if &cap[1] == "sizes" {
+                return cap[0].to_string();
+            }
+            let candidates: Vec<String> = cap[2]
+                .split(',')
+                .map(|candidate| {
+                    let candidate = candidate.trim();
+                    match candidate.split_once(char::is_whitespace) {
+                        Some((url, descriptor)) => format!("{} {}", process_upload_url(url), descriptor.trim()),
+                        None => process_upload_url(candidate),
+                    }
+                })
+                .collect();
+            format!(r#" srcset="{}""#, candidates.join(", "))
+        })
+        .into_owned()
+}
+
+// Só mexe nas URLs do WordPress; imagens externas ficam como estão
+fn process_upload_url(url: &str) -> String {
+    if url.contains("/wp-content/") {
+        process_image_url(url)
+    } else {
+        url.to_string()
+    }
+}
+
+pub fn process_html(html: String, options: &HtmlOptions) -> String {
     let regex_image = match Regex::new(r#"<img[^>]+src="([^">]+)"#) {
         Ok(regex) => regex,
         Err(err) => {
@@ -213,7 +158,10 @@ pub async fn process_html(html: String, client: Client) -> String {
             None => continue,
         };
 
-        let new_url = process_image_url(image_url);
+        let mut new_url = process_image_url(image_url);
+        if options.collapse_sizes {
+            new_url = collapse_size_variant(&new_url);
+        }
         info!(
             "process_html:  image image {} to new_url: {}",
             image_url, new_url
@@ -234,54 +182,126 @@ pub async fn process_html(html: String, client: Client) -> String {
         );
         processed_html = processed_html.replace(pdf_url, &new_url);
     }
-    processed_html
+    process_srcset(&processed_html, options)
+}
+
+/// Aplica o pipeline de HTML e imagens, gerando o post como será enviado.
+pub fn prepare_post(post: PostData, mapping: &TagMapping, html: &HtmlOptions) -> PostData {
+    let processed_html = process_html(post.html.to_string(), html);
+    let tags = mapping
+        .order_terms(&post.terms)
+        .into_iter()
+        .map(TagRef::from)
+        .collect();
+    let post = PostData { tags, ..post };
+    post.sanitize(processed_html)
 }
 
-async fn process_post(client: Client, post: PostData) {
-    let client_clone_image = client.clone();
-    let client_clone_post = client.clone();
+/// Como `prepare_post`, reescrevendo antes os links internos no HTML original,
+/// para que os links criados pelo pipeline (anúncio, `/content/images`) não sejam lidos.
+pub fn prepare_post_with_links(
+    post: PostData,
+    mapping: &TagMapping,
+    html: &HtmlOptions,
+    links: Option<&LinkRewriter>,
+) -> PostData {
+    let post = match links {
+        Some(links) => PostData {
+            html: links.rewrite_html(post.id, &post.html),
+            ..post
+        },
+        None => post,
+    };
+    prepare_post(post, mapping, html)
+}
 
-    let handle = tokio::spawn(async move {
-        let processed_html = process_html(post.html.to_string(), client_clone_image).await;
-        let post_sanitize = post.sanitize(processed_html);
+async fn process_post(
+    sink: &dyn Sink,
+    post: PostData,
+    id_map: &IdMap,
+    options: &MigrateOptions,
+    links: Option<&LinkRewriter>,
+) -> bool {
+    let wp_id = post.id;
+    let mut post_sanitize =
+        prepare_post_with_links(post, &options.tag_mapping, &options.html, links).resolve_ids(id_map);
+    if let Some(media) = &options.media {
+        post_sanitize.html = media.rewrite_html(sink, post_sanitize.html).await;
+        post_sanitize.image_url = media.rewrite_url(sink, post_sanitize.image_url).await;
+    }
+    // No sync, posts já migrados são atualizados
+    let remote_id = id_map.get(Entity::Posts, wp_id).filter(|_| options.sync);
+    let reply = match remote_id {
+        Some(remote_id) => sink.update_post(&remote_id, &post_sanitize).await,
+        None => sink.send_post(&post_sanitize).await,
+    };
+    record_reply(id_map, Entity::Posts, wp_id, reply)
+}
 
-        if let Some(post_saved) = send_post(client_clone_post, post_sanitize).await {
-            info!("Post reply received: {:?}", &post_saved.id);
-        } else {
-            error!("No post reply received");
+// Os termos do post só têm o próprio nome; o caminho vem da lista de tags
+async fn flatten_categories(source: &dyn Source, posts: &mut [PostData]) {
+    let paths = match source.tags().await {
+        Ok(tags) => category_paths(&tags),
+        Err(message) => {
+            error!("Tags not found, keeping category names: {:?}", message);
+            return;
         }
-    });
-    if let Err(err) = handle.await {
-        error!("Task failed: {:?}", err);
+    };
+    for post in posts.iter_mut() {
+        flatten_terms(&mut post.terms, &paths);
     }
 }
 
-pub async fn migrate_posts() {
-    match get_posts().await {
-        Ok(posts) => {
-            info!("found {} posts from database", posts.len());
-            let client = Client::builder()
-                .min_tls_version(Version::TLS_1_2)
-                .danger_accept_invalid_certs(true)
-                .build()
-                .unwrap();
+pub async fn migrate_posts(
+    source: &dyn Source,
+    sink: Arc<dyn Sink>,
+    id_map: Arc<IdMap>,
+    options: &MigrateOptions,
+) -> MigrateSummary {
+    match source.posts(&options.filter).await {
+        Ok(mut posts) => {
+            info!("found {} posts from source", posts.len());
+            let mut summary = MigrateSummary::new(posts.iter().map(|post| post.updated_at.clone()).max());
+            if options.tag_mapping.hierarchy == Hierarchy::Flatten {
+                flatten_categories(source, &mut posts).await;
+            }
+            options.skip_migrated(&id_map, Entity::Posts, &mut posts, |post| post.id);
+            let links = link_rewriter(source, &options.filter, &options.html).await;
+            let throttle = Arc::new(options.throttle());
             let mut handles = vec![];
             for post in posts {
-                let client_clone = client.clone();
+                let sink = sink.clone();
+                let id_map = id_map.clone();
+                let throttle = throttle.clone();
+                let options = options.clone();
+                let links = links.clone();
                 let handle = tokio::spawn(async move {
-                    process_post(client_clone, post).await;
+                    let _permit = throttle.acquire().await;
+                    process_post(sink.as_ref(), post, &id_map, &options, links.as_deref()).await
                 });
                 handles.push(handle);
             }
             // Aguarda a conclusão da tarefa
             for handle in handles {
-                if let Err(err) = handle.await {
-                    error!("Fail to send process_migrate_post: {:?}", err);
+                match handle.await {
+                    Ok(ok) => summary.add(ok),
+                    Err(err) => {
+                        error!("Fail to send process_migrate_post: {:?}", err);
+                        summary.add(false);
+                    }
                 }
             }
+            if let Some(links) = links {
+                links.report();
+            }
+            summary
         }
         Err(message) => {
             error!("Posts not found: {:?}", message);
+            MigrateSummary {
+                failed: 1,
+                ..MigrateSummary::default()
+            }
         }
     }
 }
@@ -0,0 +1,132 @@
+use crate::mapping::{Entity, IdMap};
+use crate::migrate::MigrateSummary;
+use crate::{Sink, Source};
+use anyhow::{bail, Result};
+use tracing::{error, info, warn};
+
+/// O que fazer no destino com um item que saiu do WordPress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PruneAction {
+    /// Volta o item para rascunho e mantém o mapeamento.
+    #[default]
+    Unpublish,
+    /// Remove o item e o tira do mapeamento.
+    Delete,
+}
+
+/// Por que o item deixou de existir na origem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneReason {
+    /// `post_status = 'trash'`.
+    Trashed,
+    /// Não existe mais em `wp_posts`.
+    Missing,
+}
+
+/// Item migrado que foi para a lixeira ou apagado no WordPress.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PruneCandidate {
+    pub entity: Entity,
+    pub wp_id: String,
+    pub remote_id: String,
+    pub reason: PruneReason,
+}
+
+/// Compara o mapeamento de posts e páginas com o estado atual da origem.
+pub async fn find_prunable(source: &dyn Source, id_map: &IdMap) -> Result<Vec<PruneCandidate>> {
+    let mut candidates = Vec::new();
+    for entity in [Entity::Posts, Entity::Pages] {
+        let entries = id_map.entries(entity);
+        let ids: Vec<u64> = entries.keys().filter_map(|id| id.parse().ok()).collect();
+        let statuses = source.statuses(&ids).await?;
+        for (wp_id, remote_id) in entries {
+            let status = wp_id.parse().ok().and_then(|id: u64| statuses.get(&id));
+            let reason = match status.map(String::as_str) {
+                None => PruneReason::Missing,
+                Some("trash") => PruneReason::Trashed,
+                Some(_) => continue,
+            };
+            candidates.push(PruneCandidate {
+                entity,
+                wp_id,
+                remote_id,
+                reason,
+            });
+        }
+    }
+    info!("prune: {} candidates", candidates.len());
+    Ok(candidates)
+}
+
+/// Confere os candidatos `Missing` antes de aplicar a ação; `allow_missing` aceita todos.
+///
+/// Uma origem parcial (o WXR só traz o que foi exportado) não diz o que foi
+/// apagado, então os `Missing` dela são descartados. Nas outras, se mais da
+/// metade do mapeamento parece apagada, a origem provavelmente é outra ou
+/// está incompleta e nada é removido.
+pub fn check_missing(
+    candidates: Vec<PruneCandidate>,
+    id_map: &IdMap,
+    partial_source: bool,
+    allow_missing: bool,
+) -> Result<Vec<PruneCandidate>> {
+    if allow_missing {
+        return Ok(candidates);
+    }
+    let (missing, mut kept): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .partition(|candidate| candidate.reason == PruneReason::Missing);
+    if partial_source {
+        if !missing.is_empty() {
+            warn!("prune: ignoring {} items missing from a partial source", missing.len());
+        }
+        return Ok(kept);
+    }
+    let mapped = id_map.entries(Entity::Posts).len() + id_map.entries(Entity::Pages).len();
+    if missing.len() * 2 > mapped {
+        bail!(
+            "{} of {} mapped posts and pages are missing from the source, refusing to prune them",
+            missing.len(),
+            mapped
+        );
+    }
+    kept.extend(missing);
+    Ok(kept)
+}
+
+/// Aplica `action` no destino para cada candidato.
+///
+/// Só tira o item do mapeamento quando o destino confirma a remoção, então
+/// um dry-run não altera o arquivo.
+pub async fn prune(
+    sink: &dyn Sink,
+    id_map: &IdMap,
+    candidates: &[PruneCandidate],
+    action: PruneAction,
+) -> MigrateSummary {
+    let mut summary = MigrateSummary::default();
+    for candidate in candidates {
+        let reply = match action {
+            PruneAction::Delete => sink.delete(candidate.entity, &candidate.remote_id).await,
+            PruneAction::Unpublish => sink.unpublish(candidate.entity, &candidate.remote_id).await,
+        };
+        let ok = match reply {
+            Ok(Some(_)) if action == PruneAction::Delete => {
+                match id_map.remove(candidate.entity, &candidate.wp_id) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error!("Fail to update id mapping: {:?}", e);
+                        false
+                    }
+                }
+            }
+            Ok(_) => true,
+            Err(e) => {
+                error!("Fail to prune {:?} {}: {:?}", candidate.entity, candidate.wp_id, e);
+                false
+            }
+        };
+        summary.add(ok);
+    }
+    summary
+}
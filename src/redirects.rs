@@ -0,0 +1,328 @@
+use crate::authors::AuthorPost;
+use crate::filter::Filter;
+use crate::pages::PageData;
+use crate::posts::PostData;
+use crate::tags::{TagData, Taxonomy};
+use crate::Source;
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+/// Endereço antigo do WordPress e o novo no destino.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Redirect {
+    /// Caminho antigo, ex: `/2021/05/greve-geral/` ou `/?p=12`.
+    pub from: String,
+    pub to: String,
+}
+
+impl Redirect {
+    // Links de permalink "simples" (`/?p=12`) só diferem pela query string
+    fn query(&self) -> Option<&str> {
+        self.from.strip_prefix("/?")
+    }
+}
+
+/// Opções de permalink do WordPress (`wp_options`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Permalinks {
+    /// `permalink_structure`; vazia é o permalink simples (`/?p=ID`).
+    pub structure: String,
+    /// `category_base`; vazio usa `category`.
+    pub category_base: String,
+    /// `tag_base`; vazio usa `tag`.
+    pub tag_base: String,
+}
+
+impl Permalinks {
+    fn plain(&self) -> bool {
+        self.structure.is_empty()
+    }
+
+    // Arquivos seguem a barra final da estrutura dos posts
+    fn slash(&self) -> &'static str {
+        if self.structure.ends_with('/') {
+            "/"
+        } else {
+            ""
+        }
+    }
+
+    fn archive(&self, base: &str, default: &str, path: &str) -> String {
+        let base = base.trim_matches('/');
+        let base = if base.is_empty() { default } else { base };
+        format!("/{}/{}{}", base, path, self.slash())
+    }
+
+    /// Arquivo de uma categoria ou tag, ex: `/category/internacional/europa/`.
+    pub fn term_path(&self, tag: &TagData, category_path: Option<&str>) -> String {
+        match (tag.taxonomy, self.plain()) {
+            (Taxonomy::Category, true) => format!("/?cat={}", tag.id),
+            (Taxonomy::PostTag, true) => format!("/?tag={}", tag.slug),
+            (Taxonomy::Category, false) => {
+                self.archive(&self.category_base, "category", category_path.unwrap_or(&tag.slug))
+            }
+            (Taxonomy::PostTag, false) => self.archive(&self.tag_base, "tag", &tag.slug),
+        }
+    }
+
+    /// Arquivo de um autor pelo `user_nicename`, ex: `/author/maria-silva/`.
+    pub fn author_path(&self, author: &AuthorPost) -> String {
+        if self.plain() {
+            format!("/?author={}", author.id)
+        } else {
+            self.archive("", "author", &author.nicename)
+        }
+    }
+}
+
+/// Endereços novos e opções que substituem as da origem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectOptions {
+    /// Substitui a `permalink_structure` da origem (obrigatório no WXR).
+    pub structure: Option<String>,
+    pub category_base: Option<String>,
+    pub tag_base: Option<String>,
+    /// Novo endereço de posts e páginas, ex: `/{slug}/`.
+    pub post_format: String,
+    /// Novo endereço das categorias, que viram tags no destino.
+    pub category_format: String,
+    pub tag_format: String,
+    pub author_format: String,
+}
+
+impl Default for RedirectOptions {
+    fn default() -> Self {
+        Self {
+            structure: None,
+            category_base: None,
+            tag_base: None,
+            post_format: String::from("/{slug}/"),
+            category_format: String::from("/tag/{slug}/"),
+            tag_format: String::from("/tag/{slug}/"),
+            author_format: String::from("/author/{slug}/"),
+        }
+    }
+}
+
+/// Caminho de um post segundo a `permalink_structure` do WordPress.
+///
+/// Estrutura vazia é o permalink simples, `/?p=ID`.
+pub fn post_path(structure: &str, post: &PostData, category: Option<&str>, author: Option<&str>) -> String {
+    if structure.is_empty() {
+        return format!("/?p={}", post.id);
+    }
+    let date = |start: usize, end: usize| post.created_at.get(start..end).unwrap_or_default();
+    let path = structure
+        .replace("%year%", date(0, 4))
+        .replace("%monthnum%", date(5, 7))
+        .replace("%day%", date(8, 10))
+        .replace("%hour%", date(11, 13))
+        .replace("%minute%", date(14, 16))
+        .replace("%second%", date(17, 19))
+        .replace("%postname%", &post.slug)
+        .replace("%post_id%", &post.id.to_string())
+        .replace("%category%", category.unwrap_or("uncategorized"))
+        .replace("%author%", author.unwrap_or_default());
+    format!("/{}", path.trim_start_matches('/'))
+}
+
+/// Caminho de uma página: slugs dos pais e o da própria página, ex: `/sobre/equipe/`.
+pub fn page_path(structure: &str, page: &PageData, pages: &HashMap<String, &PageData>) -> String {
+    if structure.is_empty() {
+        return format!("/?page_id={}", page.id);
+    }
+    let mut slugs = vec![page.slug.as_str()];
+    let mut current = page.parent_id.as_ref();
+    while let Some(parent) = current.and_then(|id| pages.get(id)) {
+        // Proteção contra ciclos em post_parent
+        if slugs.len() > pages.len() {
+            break;
+        }
+        slugs.push(parent.slug.as_str());
+        current = parent.parent_id.as_ref();
+    }
+    slugs.reverse();
+    // Mesma barra final dos posts
+    let slash = if structure.ends_with('/') { "/" } else { "" };
+    format!("/{}{}", slugs.join("/"), slash)
+}
+
+// Slug de cada categoria com os pais, como o WordPress usa em %category%
+fn category_slugs(tags: &[TagData]) -> HashMap<i32, String> {
+    let by_id: HashMap<String, &TagData> = tags.iter().map(|tag| (tag.id.to_string(), tag)).collect();
+    tags.iter()
+        .map(|tag| {
+            let mut slugs = vec![tag.slug.as_str()];
+            let mut current = tag.parent_id.as_ref();
+            while let Some(parent) = current.and_then(|id| by_id.get(id)) {
+                if slugs.len() > tags.len() {
+                    break;
+                }
+                slugs.push(parent.slug.as_str());
+                current = parent.parent_id.as_ref();
+            }
+            slugs.reverse();
+            (tag.id, slugs.join("/"))
+        })
+        .collect()
+}
+
+/// Monta os redirecionamentos de posts, páginas, categorias, tags e autores.
+///
+/// As opções de permalink vêm da origem, exceto as passadas em `options`.
+pub async fn build_redirects(source: &dyn Source, filter: &Filter, options: &RedirectOptions) -> Result<Vec<Redirect>> {
+    let from_source = source.permalinks().await?;
+    let Some(structure) = options
+        .structure
+        .clone()
+        .or(from_source.as_ref().map(|permalinks| permalinks.structure.clone()))
+    else {
+        bail!("permalink_structure unknown for this source, use --permalink-structure");
+    };
+    let from_source = from_source.unwrap_or_default();
+    let permalinks = Permalinks {
+        structure,
+        category_base: options.category_base.clone().unwrap_or(from_source.category_base),
+        tag_base: options.tag_base.clone().unwrap_or(from_source.tag_base),
+    };
+    info!("permalinks {:?}", permalinks);
+    let structure = &permalinks.structure;
+
+    let tags = source.tags().await?;
+    let categories = category_slugs(&tags);
+    let authors = source.authors(filter).await?;
+    // %author% também usa o user_nicename
+    let nicenames: HashMap<String, String> = authors
+        .iter()
+        .map(|author| (author.id.to_string(), author.nicename.clone()))
+        .collect();
+
+    let mut redirects = vec![];
+    for post in source.posts(filter).await? {
+        // Como o WordPress: a categoria de menor id
+        let category = post
+            .terms
+            .iter()
+            .filter(|term| term.taxonomy == Taxonomy::Category)
+            .min_by_key(|term| term.id)
+            .and_then(|term| categories.get(&term.id));
+        let author = nicenames.get(&post.author_id);
+        redirects.push(Redirect {
+            from: post_path(structure, &post, category.map(String::as_str), author.map(String::as_str)),
+            to: options.post_format.replace("{slug}", &post.slug),
+        });
+    }
+    let pages = source.pages(filter).await?;
+    let by_id: HashMap<String, &PageData> = pages.iter().map(|page| (page.id.to_string(), page)).collect();
+    for page in &pages {
+        redirects.push(Redirect {
+            from: page_path(structure, page, &by_id),
+            to: options.post_format.replace("{slug}", &page.slug),
+        });
+    }
+    for tag in &tags {
+        let format = match tag.taxonomy {
+            Taxonomy::Category => &options.category_format,
+            Taxonomy::PostTag => &options.tag_format,
+        };
+        redirects.push(Redirect {
+            from: permalinks.term_path(tag, categories.get(&tag.id).map(String::as_str)),
+            to: format.replace("{slug}", &tag.slug),
+        });
+    }
+    for author in &authors {
+        redirects.push(Redirect {
+            from: permalinks.author_path(author),
+            to: options.author_format.replace("{slug}", &author.nicename),
+        });
+    }
+
+    redirects.retain(|redirect| redirect.from != redirect.to);
+    redirects.sort();
+    redirects.dedup_by(|a, b| a.from == b.from);
+    Ok(redirects)
+}
+
+// Regex do caminho antigo, aceitando com e sem a barra final
+fn path_regex(redirect: &Redirect) -> String {
+    match redirect.query() {
+        Some(query) => format!("^/\\?{}$", regex::escape(query)),
+        None => format!("^{}/?$", regex::escape(redirect.from.trim_end_matches('/'))),
+    }
+}
+
+fn yaml_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// `redirects.yaml` do Ghost (Configurações → Labs → Redirects).
+pub fn ghost_yaml(redirects: &[Redirect]) -> String {
+    let mut yaml = String::from("301:\n");
+    for redirect in redirects {
+        yaml.push_str(&format!("  {}: {}\n", yaml_quote(&path_regex(redirect)), yaml_quote(&redirect.to)));
+    }
+    yaml.push_str("\n302:\n");
+    yaml
+}
+
+/// `redirects.json` do Ghost, para versões que ainda usam o formato antigo.
+pub fn ghost_json(redirects: &[Redirect]) -> Result<String> {
+    let redirects: Vec<_> = redirects
+        .iter()
+        .map(|redirect| json!({"from": path_regex(redirect), "to": redirect.to, "permanent": true}))
+        .collect();
+    serde_json::to_string_pretty(&redirects).context("Failed to serialize redirects")
+}
+
+/// Bloco `map` do nginx; use no `server` com `if ($redirect_uri) { return 301 $redirect_uri; }`.
+pub fn nginx_map(redirects: &[Redirect]) -> String {
+    let mut map = String::from("map $request_uri $redirect_uri {\n    default \"\";\n");
+    for redirect in redirects {
+        let pattern = match redirect.query() {
+            Some(query) => format!("~^/\\?{}(&.*)?$", regex::escape(query)),
+            None => format!("~^{}/?(\\?.*)?$", regex::escape(redirect.from.trim_end_matches('/'))),
+        };
+        map.push_str(&format!("    \"{}\" {};\n", pattern, redirect.to));
+    }
+    map.push_str("}\n");
+    map
+}
+
+/// Regras de `mod_rewrite` para o `.htaccess` do Apache.
+pub fn htaccess(redirects: &[Redirect]) -> String {
+    let mut rules = String::from("RewriteEngine On\n");
+    for redirect in redirects {
+        match redirect.query() {
+            Some(query) => {
+                rules.push_str(&format!("RewriteCond %{{QUERY_STRING}} (^|&){}(&|$)\n", regex::escape(query)));
+                // O `?` no fim descarta a query string antiga
+                rules.push_str(&format!("RewriteRule ^$ {}? [R=301,L]\n", redirect.to));
+            }
+            None => {
+                let path = redirect.from.trim_start_matches('/').trim_end_matches('/');
+                rules.push_str(&format!("RewriteRule ^{}/?$ {} [R=301,L]\n", regex::escape(path), redirect.to));
+            }
+        }
+    }
+    rules
+}
+
+/// Grava `redirects.yaml`, `redirects.json`, `redirects-nginx.conf` e `.htaccess` em `dir`.
+pub fn write_redirects(dir: &Path, redirects: &[Redirect]) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create directory {}", dir.display()))?;
+    let files = [
+        ("redirects.yaml", ghost_yaml(redirects)),
+        ("redirects.json", ghost_json(redirects)?),
+        ("redirects-nginx.conf", nginx_map(redirects)),
+        (".htaccess", htaccess(redirects)),
+    ];
+    for (name, content) in files {
+        let path = dir.join(name);
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
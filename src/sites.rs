@@ -0,0 +1,42 @@
+use crate::cli::{SourceArgs, SourceKind};
+use tracing::error;
+
+pub async fn list_sites(args: &SourceArgs) {
+    if let SourceKind::Wxr = args.source {
+        eprintln!("O comando sites só funciona com --source mysql.");
+        return;
+    }
+    let sites = match args.mysql().and_then(|source| source.sites()) {
+        Ok(sites) => sites,
+        Err(e) => {
+            error!("Fail to list sites: {:?}", e);
+            eprintln!("Não foi possível ler a tabela de sites (a instalação é multisite?).");
+            return;
+        }
+    };
+
+    println!("{:<8} {:<40} status", "blog_id", "site");
+    for site in sites {
+        let mut status = vec![];
+        if !site.public {
+            status.push("privado");
+        }
+        if site.archived {
+            status.push("arquivado");
+        }
+        if site.deleted {
+            status.push("removido");
+        }
+        let status = if status.is_empty() {
+            String::from("ativo")
+        } else {
+            status.join(", ")
+        };
+        println!(
+            "{:<8} {:<40} {}",
+            site.blog_id,
+            format!("{}{}", site.domain, site.path),
+            status
+        );
+    }
+}
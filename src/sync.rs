@@ -0,0 +1,124 @@
+use crate::authors::migrate_authors;
+use crate::filter::Filter;
+use crate::mapping::{Entity, IdMap};
+use crate::migrate::{MigrateOptions, MigrateSummary};
+use crate::pages::migrate_pages;
+use crate::posts::migrate_posts;
+use crate::tags::migrate_tags;
+use crate::{Sink, Source};
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Marca da última sincronização bem-sucedida: o maior `post_modified` já
+/// enviado de cada entidade.
+///
+/// Usa o relógio do próprio WordPress, então não depende do fuso de quem roda.
+#[derive(Debug)]
+pub struct SyncState {
+    path: PathBuf,
+    last_modified: BTreeMap<Entity, String>,
+}
+
+impl SyncState {
+    /// Carrega o estado de `path`, ou começa vazio se o arquivo não existir.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let last_modified = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read sync file {}", path.display()))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse sync file {}", path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, last_modified })
+    }
+
+    /// Carrega o arquivo indicado por `SYNC_FILE` (padrão `sync.json`).
+    pub fn from_env() -> Result<Self> {
+        let path = env::var("SYNC_FILE").unwrap_or_else(|_| String::from("sync.json"));
+        Self::load(path)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<String> {
+        self.last_modified.get(&entity).cloned()
+    }
+
+    pub fn set(&mut self, entity: Entity, post_modified: String) {
+        self.last_modified.insert(entity, post_modified);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.last_modified)
+            .context("Failed to serialize sync state")?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)
+            .with_context(|| format!("Failed to write sync file {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Failed to replace sync file {}", self.path.display()))
+    }
+
+    // Só avança a marca quando nada falhou, para reenviar os itens na próxima vez
+    fn advance(&mut self, entity: Entity, summary: &MigrateSummary) {
+        if summary.failed > 0 {
+            warn!(
+                "{} {:?} failed, keeping last sync mark {:?}",
+                summary.failed,
+                entity,
+                self.get(entity)
+            );
+            return;
+        }
+        if let Some(latest) = &summary.latest_modified {
+            self.set(entity, latest.clone());
+        }
+    }
+}
+
+/// Envia o que mudou no WordPress desde a última sincronização.
+///
+/// Tags e autores novos são criados (como no `--resume`); posts e páginas
+/// alterados desde a marca são criados ou, se já migrados, atualizados.
+/// O estado não é gravado aqui: quem chama decide (ex: não gravar no dry-run).
+pub async fn sync(
+    source: &dyn Source,
+    sink: Arc<dyn Sink>,
+    id_map: Arc<IdMap>,
+    state: &mut SyncState,
+    options: &MigrateOptions,
+) -> (MigrateSummary, MigrateSummary) {
+    let resume = MigrateOptions {
+        resume: true,
+        sync: false,
+        ..options.clone()
+    };
+    migrate_tags(source, sink.clone(), id_map.clone(), &resume).await;
+    migrate_authors(source, sink.clone(), id_map.clone(), &resume).await;
+
+    let changed = |entity: Entity| MigrateOptions {
+        resume: false,
+        sync: true,
+        filter: Filter {
+            modified_since: state.get(entity),
+            ..options.filter.clone()
+        },
+        ..options.clone()
+    };
+    let posts_options = changed(Entity::Posts);
+    let pages_options = changed(Entity::Pages);
+    info!(
+        "sync: posts modified since {:?}, pages modified since {:?}",
+        posts_options.filter.modified_since, pages_options.filter.modified_since
+    );
+
+    let posts = migrate_posts(source, sink.clone(), id_map.clone(), &posts_options).await;
+    state.advance(Entity::Posts, &posts);
+    let pages = migrate_pages(source, sink, id_map, &pages_options).await;
+    state.advance(Entity::Pages, &pages);
+    (posts, pages)
+}
@@ -1,83 +1,240 @@
-use dotenv::dotenv;
-use mysql::{prelude::*, Pool};
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION};
-use reqwest::Client;
-use reqwest::tls::Version;
-use serde::Serialize;
-use std::env;
-use tracing::{error, info};
-
-#[derive(Debug, Serialize)]
-struct TagData {
-    id: i32,
-    name: String,
-    slug: String,
+use crate::mapping::{Entity, IdMap};
+use crate::migrate::{group_by_depth, record_reply, MigrateOptions};
+use crate::{Sink, Source};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// Taxonomia de origem do termo no WordPress.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Taxonomy {
+    #[default]
+    Category,
+    PostTag,
 }
 
-async fn send_tag(client: Client, tag: TagData) {
-    let token = env::var("API_TOKEN").unwrap();
-    let api_url = env::var("API_URL").unwrap();
-    let url_req = format!("{}/tags", &api_url);
-    info!("send tag: { }", tag.name);
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
-    );
-    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
-    info!("config request");
-    let res = client
-        .post(url_req)
-        .headers(headers)
-        .json(&tag)
-        .send()
-        .await;
-
-    match res {
-        Ok(response) => info!("Tag enviada com sucesso: {:?}", response),
-        Err(e) => error!("Erro ao enviar tag: {:?}", e),
+impl Taxonomy {
+    /// Converte o valor de `wp_term_taxonomy.taxonomy` (ou do `domain` no WXR).
+    pub fn from_wp(taxonomy: &str) -> Option<Self> {
+        match taxonomy {
+            "category" => Some(Taxonomy::Category),
+            "post_tag" => Some(Taxonomy::PostTag),
+            _ => None,
+        }
     }
 }
 
-pub async fn migrate_tags() {
-    dotenv().ok();
-    let db_url = env::var("DB_URL").unwrap();
-    let connection_opts = mysql::Opts::from_url(&db_url).unwrap();
-    let pool = Pool::new(connection_opts).unwrap();
-    let mut conn = pool.get_conn().unwrap();
-
-    let tags: Vec<TagData> = conn
-        .query_map(
-            "SELECT
-                 t.term_id AS id,
-                 t.name AS name,
-                 t.slug AS slug
-             FROM
-                 wp_terms t
-             JOIN
-                 wp_term_taxonomy tt ON t.term_id = tt.term_id
-             WHERE
-                 tt.taxonomy = 'category'",
-            |(id, name, slug)| TagData { id, name, slug },
-        )
-        .unwrap();
-
-    info!("ok query tags");
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .min_tls_version(Version::TLS_1_2)
-        .build()
-        .unwrap();
-    let mut handles = vec![];
-    for tag in tags {
-        let client_clone = client.clone();
-        let handle = tokio::spawn(async move {
-            send_tag(client_clone, tag).await;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagData {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+    pub description: String,
+    /// Quantidade de posts no WordPress.
+    pub count: u64,
+    /// Id da categoria pai; o do WordPress até ser resolvido para o da API.
+    pub parent_id: Option<String>,
+    #[serde(skip)]
+    pub taxonomy: Taxonomy,
+}
+
+impl TagData {
+    // Troca o id do pai no WordPress pelo id já criado na API; sem ele a categoria vai na raiz
+    fn resolve_parent(self, id_map: &IdMap) -> Self {
+        let parent_id = self.parent_id.and_then(|parent_id| {
+            let resolved = id_map.get(Entity::Tags, &parent_id);
+            if resolved.is_none() {
+                warn!("parent {} of tag {} not migrated yet, sending at the root", parent_id, self.slug);
+            }
+            resolved
         });
-        handles.push(handle);
+        Self { parent_id, ..self }
+    }
+}
+
+/// Nome de cada categoria com o caminho dos pais, ex: "Internacional / Europa".
+pub fn category_paths(tags: &[TagData]) -> HashMap<i32, String> {
+    let by_id: HashMap<String, &TagData> = tags.iter().map(|tag| (tag.id.to_string(), tag)).collect();
+    tags.iter()
+        .map(|tag| {
+            let mut names = vec![tag.name.as_str()];
+            let mut current = tag.parent_id.as_ref();
+            while let Some(parent) = current.and_then(|id| by_id.get(id)) {
+                // Proteção contra ciclos em wp_term_taxonomy
+                if names.len() > tags.len() {
+                    break;
+                }
+                names.push(parent.name.as_str());
+                current = parent.parent_id.as_ref();
+            }
+            names.reverse();
+            (tag.id, names.join(" / "))
+        })
+        .collect()
+}
+
+/// Troca os nomes pelo caminho completo e remove o pai (`Hierarchy::Flatten`).
+pub fn flatten_terms(terms: &mut [TagData], paths: &HashMap<i32, String>) {
+    for term in terms.iter_mut() {
+        if let Some(path) = paths.get(&term.id) {
+            term.name = path.clone();
+        }
+        term.parent_id = None;
+    }
+}
+
+/// Tag como vai no payload do post.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TagRef {
+    /// Id da tag na API; ausente quando a tag ainda não foi migrada.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub slug: String,
+    pub name: String,
+    #[serde(skip)]
+    pub wp_id: i32,
+}
+
+impl From<TagData> for TagRef {
+    fn from(tag: TagData) -> Self {
+        Self {
+            id: None,
+            slug: tag.slug,
+            name: tag.name,
+            wp_id: tag.id,
+        }
     }
+}
 
-    for handle in handles {
-        handle.await.unwrap();
+impl TagRef {
+    /// Troca pelo id já criado na API por `migrate_tags`, se houver.
+    pub fn resolve(self, id_map: &IdMap) -> Self {
+        match id_map.get(Entity::Tags, self.wp_id) {
+            Some(id) => Self { id: Some(id), ..self },
+            None => {
+                warn!("tag {} not migrated yet, sending only slug and name", self.slug);
+                self
+            }
+        }
+    }
+}
+
+/// Papel que os termos de uma taxonomia assumem no destino.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TagRole {
+    /// Vêm primeiro na lista de tags do post.
+    Primary,
+    /// Vêm depois das primárias.
+    Secondary,
+    /// Tags internas (`#nome`), que não aparecem no site.
+    Internal,
+    /// Não são migradas.
+    Skip,
+}
+
+/// Como a hierarquia de categorias chega ao destino.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Hierarchy {
+    /// Envia o id da categoria pai.
+    #[default]
+    Parent,
+    /// Junta os nomes dos pais no nome da tag, sem pai.
+    Flatten,
+}
+
+/// Define o papel de categorias e tags do WordPress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagMapping {
+    pub categories: TagRole,
+    pub post_tags: TagRole,
+    pub hierarchy: Hierarchy,
+}
+
+impl Default for TagMapping {
+    fn default() -> Self {
+        Self {
+            categories: TagRole::Primary,
+            post_tags: TagRole::Secondary,
+            hierarchy: Hierarchy::default(),
+        }
+    }
+}
+
+impl TagMapping {
+    pub fn role(&self, taxonomy: Taxonomy) -> TagRole {
+        match taxonomy {
+            Taxonomy::Category => self.categories,
+            Taxonomy::PostTag => self.post_tags,
+        }
+    }
+
+    /// Aplica o papel ao termo; `None` quando a taxonomia não é migrada.
+    pub fn apply(&self, tag: TagData) -> Option<TagData> {
+        match self.role(tag.taxonomy) {
+            TagRole::Skip => None,
+            TagRole::Internal => Some(TagData {
+                name: format!("#{}", tag.name.trim_start_matches('#')),
+                slug: format!("hash-{}", tag.slug),
+                ..tag
+            }),
+            TagRole::Primary | TagRole::Secondary => Some(tag),
+        }
+    }
+
+    /// Tags de um post na ordem de envio: primárias, secundárias e internas,
+    /// mantendo a ordem da origem dentro de cada grupo.
+    pub fn order_terms(&self, terms: &[TagData]) -> Vec<TagData> {
+        let mut terms: Vec<TagData> = terms.to_vec();
+        terms.sort_by_key(|term| self.role(term.taxonomy));
+        terms.into_iter().filter_map(|term| self.apply(term)).collect()
+    }
+}
+
+pub async fn migrate_tags(
+    source: &dyn Source,
+    sink: Arc<dyn Sink>,
+    id_map: Arc<IdMap>,
+    options: &MigrateOptions,
+) {
+    let mut tags = match source.tags().await {
+        Ok(tags) => tags,
+        Err(message) => {
+            error!("Tags not found: {:?}", message);
+            return;
+        }
+    };
+
+    info!("found {} tags from source", tags.len());
+    let mapping = options.tag_mapping;
+    if mapping.hierarchy == Hierarchy::Flatten {
+        let paths = category_paths(&tags);
+        flatten_terms(&mut tags, &paths);
+    }
+    tags = tags.into_iter().filter_map(|tag| mapping.apply(tag)).collect();
+    options.skip_migrated(&id_map, Entity::Tags, &mut tags, |tag| tag.id);
+    let throttle = Arc::new(options.throttle());
+    for level in group_by_depth(tags, |tag| tag.id.to_string(), |tag| tag.parent_id.clone()) {
+        let mut handles = vec![];
+        for tag in level {
+            let sink = sink.clone();
+            let id_map = id_map.clone();
+            let throttle = throttle.clone();
+            let handle = tokio::spawn(async move {
+                let _permit = throttle.acquire().await;
+                let wp_id = tag.id;
+                let tag = tag.resolve_parent(&id_map);
+                let reply = sink.send_tag(&tag).await;
+                record_reply(&id_map, Entity::Tags, wp_id, reply);
+            });
+            handles.push(handle);
+        }
+        // Aguarda o nível inteiro antes de enviar as categorias filhas
+        for handle in handles {
+            if let Err(e) = handle.await {
+                error!("Task failed: {:?}", e);
+            }
+        }
     }
 }
@@ -0,0 +1,43 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::{sleep_until, Instant};
+
+/// Limita quantas requisições ficam em andamento ao mesmo tempo e quantas
+/// começam por segundo. Compartilhado entre as tasks via `Arc`.
+#[derive(Debug)]
+pub struct Throttle {
+    semaphore: Semaphore,
+    period: Option<Duration>,
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    /// `rate_limit` em requisições por segundo; `None` não limita.
+    pub fn new(concurrency: usize, rate_limit: Option<f64>) -> Self {
+        Self {
+            semaphore: Semaphore::new(concurrency.max(1)),
+            period: rate_limit
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            next_slot: Mutex::new(None),
+        }
+    }
+
+    /// Espera uma vaga livre e o próximo horário permitido. A vaga é liberada
+    /// quando o permit retornado sai de escopo.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self.semaphore.acquire().await.unwrap();
+        if let Some(period) = self.period {
+            let slot = {
+                let mut next_slot = self.next_slot.lock().unwrap();
+                let now = Instant::now();
+                let slot = next_slot.map_or(now, |next| next.max(now));
+                *next_slot = Some(slot + period);
+                slot
+            };
+            sleep_until(slot).await;
+        }
+        permit
+    }
+}
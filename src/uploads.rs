@@ -0,0 +1,41 @@
+use anyhow::Result;
+use killer::filter::Filter;
+use killer::media::{copy_uploads, referenced_uploads, write_manifest};
+use killer::Source;
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::{error, info};
+
+// Arquivos citados pelos posts e páginas selecionados
+async fn referenced(source: &dyn Source, filter: &Filter) -> Result<HashSet<String>> {
+    let mut paths = HashSet::new();
+    for post in source.posts(filter).await? {
+        paths.extend(referenced_uploads(&post.html, post.image_url.as_deref()));
+    }
+    for page in source.pages(filter).await? {
+        paths.extend(referenced_uploads(&page.html, page.image_url.as_deref()));
+    }
+    info!("{} files referenced by posts and pages", paths.len());
+    Ok(paths)
+}
+
+/// Copia os uploads do WordPress para `output` e grava o manifesto.
+///
+/// Com `source` (`--referenced-only`), só os arquivos citados pelos posts e páginas de `filter`.
+pub async fn copy_media(source: Option<&dyn Source>, filter: &Filter, uploads_dir: &Path, output: &Path, manifest: &Path) {
+    let result = async {
+        let referenced = match source {
+            Some(source) => Some(referenced(source, filter).await?),
+            None => None,
+        };
+        let copied = copy_uploads(uploads_dir, output, referenced.as_ref())?;
+        write_manifest(manifest, &copied)?;
+        Ok::<_, anyhow::Error>(copied.len())
+    }
+    .await;
+
+    match result {
+        Ok(copied) => println!("{} arquivos em {}, manifesto em {}", copied, output.display(), manifest.display()),
+        Err(e) => error!("Fail to copy media: {:?}", e),
+    }
+}
@@ -0,0 +1,538 @@
+use crate::authors::{self, AuthorPost};
+use crate::filter::{Filter, PostStatus};
+use crate::pages::PageData;
+use crate::posts::PostData;
+use crate::redirects::Permalinks;
+use crate::tags::{TagData, Taxonomy};
+use crate::Source;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+lazy_static! {
+    // Atributos de uma tag, ex: domain="category" nicename="news"
+    static ref ATTRIBUTE: Regex = Regex::new(r#"([\w:-]+)\s*=\s*"([^"]*)""#).unwrap();
+    // Entidades numéricas, ex: &#8220; ou &#x201C;
+    static ref NUMERIC_ENTITY: Regex = Regex::new(r"&#(x[0-9a-fA-F]+|[0-9]+);").unwrap();
+}
+
+// Marcador usado no lugar de cada seção CDATA enquanto o documento é percorrido
+const CDATA_MARK: char = '\u{1}';
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WxrAuthor {
+    pub id: i32,
+    pub login: String,
+    /// `wp:author_nicename` quando o arquivo traz; senão gerado do login.
+    pub nicename: String,
+    pub email: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WxrTerm {
+    pub term_id: i32,
+    pub slug: String,
+    pub name: String,
+    pub parent: String,
+    pub description: String,
+}
+
+/// Termo associado a um item, ex: `<category domain="post_tag" nicename="x">X</category>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WxrItemTerm {
+    pub domain: String,
+    pub slug: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WxrItem {
+    pub post_id: u64,
+    pub title: String,
+    pub link: String,
+    pub guid: String,
+    pub creator: String,
+    pub content: String,
+    pub excerpt: String,
+    pub post_date: String,
+    pub post_modified: String,
+    pub post_name: String,
+    pub status: String,
+    pub post_parent: u64,
+    pub menu_order: i32,
+    pub post_type: String,
+    pub attachment_url: Option<String>,
+    pub terms: Vec<WxrItemTerm>,
+    pub meta: HashMap<String, String>,
+}
+
+/// Conteúdo de um arquivo WXR (WordPress "Ferramentas → Exportar").
+#[derive(Debug, Clone, Default)]
+pub struct WxrDocument {
+    pub authors: Vec<WxrAuthor>,
+    pub categories: Vec<WxrTerm>,
+    pub tags: Vec<WxrTerm>,
+    pub items: Vec<WxrItem>,
+    /// Arquivo de cada anexo pelo id, montado uma vez em `parse`.
+    attachments: HashMap<String, String>,
+}
+
+struct Element<'a> {
+    attributes: &'a str,
+    inner: &'a str,
+}
+
+struct Masked {
+    text: String,
+    cdata: Vec<String>,
+}
+
+impl Masked {
+    // Troca cada <![CDATA[...]]> por um marcador, para que HTML dentro do
+    // conteúdo nunca seja confundido com as tags do próprio WXR
+    fn new(xml: &str) -> Self {
+        let mut text = String::with_capacity(xml.len());
+        let mut cdata = vec![];
+        let mut rest = xml;
+        while let Some(start) = rest.find("<![CDATA[") {
+            text.push_str(&rest[..start]);
+            let after = &rest[start + 9..];
+            let end = after.find("]]>").unwrap_or(after.len());
+            text.push(CDATA_MARK);
+            text.push_str(&cdata.len().to_string());
+            text.push(CDATA_MARK);
+            cdata.push(after[..end].to_string());
+            rest = after.get(end + 3..).unwrap_or("");
+        }
+        text.push_str(rest);
+        Self { text, cdata }
+    }
+
+    // Texto de um trecho: seções CDATA voltam como estão, o resto tem as
+    // entidades XML decodificadas
+    fn text(&self, inner: &str) -> String {
+        let mut result = String::new();
+        let mut parts = inner.split(CDATA_MARK);
+        if let Some(first) = parts.next() {
+            result.push_str(&unescape(first));
+        }
+        while let (Some(index), Some(plain)) = (parts.next(), parts.next()) {
+            if let Some(section) = index.parse::<usize>().ok().and_then(|i| self.cdata.get(i)) {
+                result.push_str(section);
+            }
+            result.push_str(&unescape(plain));
+        }
+        result.trim().to_string()
+    }
+
+    fn child(&self, block: &str, tag: &str) -> String {
+        elements(block, tag)
+            .first()
+            .map(|element| self.text(element.inner))
+            .unwrap_or_default()
+    }
+}
+
+fn unescape(text: &str) -> String {
+    let text = NUMERIC_ENTITY.replace_all(text, |caps: &regex::Captures| {
+        let code = &caps[1];
+        let value = match code.strip_prefix('x') {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => code.parse::<u32>().ok(),
+        };
+        value
+            .and_then(char::from_u32)
+            .map(String::from)
+            .unwrap_or_else(|| caps[0].to_string())
+    });
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Todas as ocorrências de <tag ...>...</tag> (ou <tag/>) dentro de `xml`
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<Element<'a>> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut found = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after_name = &rest[start + open.len()..];
+        // Evita casar <category> com <category_parent>, por exemplo
+        if !after_name.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            rest = after_name;
+            continue;
+        }
+        let Some(tag_end) = after_name.find('>') else {
+            break;
+        };
+        let attributes = &after_name[..tag_end];
+        if attributes.ends_with('/') {
+            found.push(Element {
+                attributes: attributes.trim_end_matches('/'),
+                inner: "",
+            });
+            rest = &after_name[tag_end + 1..];
+            continue;
+        }
+        let body = &after_name[tag_end + 1..];
+        let Some(end) = body.find(&close) else {
+            break;
+        };
+        found.push(Element {
+            attributes,
+            inner: &body[..end],
+        });
+        rest = &body[end + close.len()..];
+    }
+    found
+}
+
+fn attribute(attributes: &str, name: &str) -> String {
+    ATTRIBUTE
+        .captures_iter(attributes)
+        .find(|caps| &caps[1] == name)
+        .map(|caps| unescape(&caps[2]))
+        .unwrap_or_default()
+}
+
+fn parse_author(masked: &Masked, block: &str) -> WxrAuthor {
+    let login = masked.child(block, "wp:author_login");
+    // O exportador do WordPress não inclui o nicename; outros plugins, sim
+    let nicename = match masked.child(block, "wp:author_nicename") {
+        nicename if nicename.is_empty() => authors::nicename(&login),
+        nicename => nicename,
+    };
+    WxrAuthor {
+        id: masked.child(block, "wp:author_id").parse().unwrap_or_default(),
+        login,
+        nicename,
+        email: masked.child(block, "wp:author_email"),
+        display_name: masked.child(block, "wp:author_display_name"),
+    }
+}
+
+fn parse_category(masked: &Masked, block: &str) -> WxrTerm {
+    WxrTerm {
+        term_id: masked.child(block, "wp:term_id").parse().unwrap_or_default(),
+        slug: masked.child(block, "wp:category_nicename"),
+        name: masked.child(block, "wp:cat_name"),
+        parent: masked.child(block, "wp:category_parent"),
+        description: masked.child(block, "wp:category_description"),
+    }
+}
+
+fn parse_tag(masked: &Masked, block: &str) -> WxrTerm {
+    WxrTerm {
+        term_id: masked.child(block, "wp:term_id").parse().unwrap_or_default(),
+        slug: masked.child(block, "wp:tag_slug"),
+        name: masked.child(block, "wp:tag_name"),
+        parent: String::new(),
+        description: masked.child(block, "wp:tag_description"),
+    }
+}
+
+fn parse_item(masked: &Masked, block: &str) -> WxrItem {
+    let terms = elements(block, "category")
+        .into_iter()
+        .map(|element| WxrItemTerm {
+            domain: attribute(element.attributes, "domain"),
+            slug: attribute(element.attributes, "nicename"),
+            name: masked.text(element.inner),
+        })
+        .collect();
+    let meta = elements(block, "wp:postmeta")
+        .into_iter()
+        .map(|element| {
+            (
+                masked.child(element.inner, "wp:meta_key"),
+                masked.child(element.inner, "wp:meta_value"),
+            )
+        })
+        .collect();
+    let post_date = masked.child(block, "wp:post_date");
+    let post_modified = Some(masked.child(block, "wp:post_modified"))
+        .filter(|modified| !modified.is_empty())
+        .unwrap_or_else(|| post_date.clone());
+    let attachment_url = Some(masked.child(block, "wp:attachment_url")).filter(|url| !url.is_empty());
+
+    WxrItem {
+        post_id: masked.child(block, "wp:post_id").parse().unwrap_or_default(),
+        title: masked.child(block, "title"),
+        link: masked.child(block, "link"),
+        guid: masked.child(block, "guid"),
+        creator: masked.child(block, "dc:creator"),
+        content: masked.child(block, "content:encoded"),
+        excerpt: masked.child(block, "excerpt:encoded"),
+        post_date,
+        post_modified,
+        post_name: masked.child(block, "wp:post_name"),
+        status: masked.child(block, "wp:status"),
+        post_parent: masked.child(block, "wp:post_parent").parse().unwrap_or_default(),
+        menu_order: masked.child(block, "wp:menu_order").parse().unwrap_or_default(),
+        post_type: masked.child(block, "wp:post_type"),
+        attachment_url,
+        terms,
+        meta,
+    }
+}
+
+impl WxrDocument {
+    pub fn parse(xml: &str) -> Self {
+        let masked = Masked::new(xml);
+        let doc = masked.text.as_str();
+
+        let items: Vec<WxrItem> = elements(doc, "item")
+            .iter()
+            .map(|element| parse_item(&masked, element.inner))
+            .collect();
+        let attachments = items
+            .iter()
+            .filter(|item| item.post_type == "attachment")
+            .filter_map(|item| {
+                let file = item
+                    .meta
+                    .get("_wp_attached_file")
+                    .cloned()
+                    .or_else(|| item.attachment_url.clone())?;
+                Some((item.post_id.to_string(), file))
+            })
+            .collect();
+
+        Self {
+            authors: elements(doc, "wp:author")
+                .iter()
+                .map(|element| parse_author(&masked, element.inner))
+                .collect(),
+            categories: elements(doc, "wp:category")
+                .iter()
+                .map(|element| parse_category(&masked, element.inner))
+                .collect(),
+            tags: elements(doc, "wp:tag")
+                .iter()
+                .map(|element| parse_tag(&masked, element.inner))
+                .collect(),
+            items,
+            attachments,
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let xml = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read WXR file {}", path.display()))?;
+        Ok(Self::parse(&xml))
+    }
+
+    /// Caminho do arquivo (`YYYY/MM/nome.jpg`) de um anexo, pelo id do anexo.
+    pub fn attachment_file(&self, attachment_id: &str) -> Option<String> {
+        self.attachments.get(attachment_id).cloned()
+    }
+}
+
+#[async_trait]
+impl Source for WxrDocument {
+    async fn authors(&self, filter: &Filter) -> Result<Vec<AuthorPost>> {
+        Ok(wxr_authors(self, filter))
+    }
+
+    async fn tags(&self) -> Result<Vec<TagData>> {
+        Ok(wxr_tags(self))
+    }
+
+    async fn posts(&self, filter: &Filter) -> Result<Vec<PostData>> {
+        Ok(wxr_posts(self, filter))
+    }
+
+    async fn pages(&self, filter: &Filter) -> Result<Vec<PageData>> {
+        Ok(wxr_pages(self, filter))
+    }
+
+    // O WXR não exporta as opções do site
+    async fn permalinks(&self) -> Result<Option<Permalinks>> {
+        Ok(None)
+    }
+
+    async fn statuses(&self, ids: &[u64]) -> Result<HashMap<u64, String>> {
+        Ok(self
+            .items
+            .iter()
+            .filter(|item| ids.contains(&item.post_id))
+            .map(|item| (item.post_id, item.status.clone()))
+            .collect())
+    }
+}
+
+// Mesmas condições da consulta do MySQL sobre wp_posts
+fn selected<'a>(
+    document: &'a WxrDocument,
+    post_type: &'a str,
+    filter: &'a Filter,
+) -> impl Iterator<Item = &'a WxrItem> {
+    let author_login = filter.author.and_then(|author| {
+        document
+            .authors
+            .iter()
+            .find(|candidate| candidate.id as u64 == author)
+            .map(|candidate| candidate.login.as_str())
+    });
+    document.items.iter().filter(move |item| {
+        item.post_type == post_type
+            && filter.accepts_status(&item.status)
+            && filter.accepts_id(item.post_id)
+            && filter.accepts_date(&item.post_date)
+            && filter.accepts_modified(&item.post_modified)
+            && (filter.author.is_none() || author_login == Some(item.creator.as_str()))
+            && (post_type != "post"
+                || filter.category.as_ref().is_none_or(|slug| {
+                    item.terms
+                        .iter()
+                        .any(|term| term.domain == "category" && &term.slug == slug)
+                }))
+    })
+}
+
+fn author_ids(document: &WxrDocument) -> HashMap<&str, String> {
+    document
+        .authors
+        .iter()
+        .map(|author| (author.login.as_str(), author.id.to_string()))
+        .collect()
+}
+
+fn featured_image(document: &WxrDocument, item: &WxrItem) -> Option<String> {
+    item.meta
+        .get("_thumbnail_id")
+        .and_then(|attachment_id| document.attachment_file(attachment_id))
+}
+
+// Mesmos critérios da consulta do MySQL: autores com posts aceitos pelo filtro
+fn wxr_authors(document: &WxrDocument, filter: &Filter) -> Vec<AuthorPost> {
+    let with_posts: HashSet<&str> = selected(document, "post", filter)
+        .map(|item| item.creator.as_str())
+        .collect();
+    document
+        .authors
+        .iter()
+        .filter(|author| with_posts.contains(author.login.as_str()) && !author.email.is_empty())
+        .map(|author| AuthorPost {
+            id: author.id,
+            name: author.display_name.clone(),
+            email: author.email.clone(),
+            login: author.login.clone(),
+            nicename: author.nicename.clone(),
+            // O WXR não exporta os papéis dos usuários
+            role: None,
+            password: String::new(),
+            created_at: String::new(),
+            image_url: None,
+        })
+        .collect()
+}
+
+fn wxr_tags(document: &WxrDocument) -> Vec<TagData> {
+    // O WXR não traz a contagem, então ela é refeita a partir dos posts publicados
+    let mut counts: HashMap<(&str, &str), u64> = HashMap::new();
+    let published = Filter::default();
+    for item in selected(document, "post", &published) {
+        for term in &item.terms {
+            *counts.entry((term.domain.as_str(), term.slug.as_str())).or_default() += 1;
+        }
+    }
+    // A categoria pai vem pelo slug
+    let category_ids: HashMap<&str, i32> = document
+        .categories
+        .iter()
+        .map(|category| (category.slug.as_str(), category.term_id))
+        .collect();
+
+    let categories = document.categories.iter().map(|term| (term, Taxonomy::Category, "category"));
+    let tags = document.tags.iter().map(|term| (term, Taxonomy::PostTag, "post_tag"));
+    categories
+        .chain(tags)
+        .map(|(term, taxonomy, domain)| TagData {
+            id: term.term_id,
+            name: term.name.clone(),
+            slug: term.slug.clone(),
+            description: term.description.clone(),
+            count: counts.get(&(domain, term.slug.as_str())).copied().unwrap_or_default(),
+            parent_id: category_ids
+                .get(term.parent.as_str())
+                .map(|parent_id| parent_id.to_string()),
+            taxonomy,
+        })
+        .collect()
+}
+
+// Os termos do item só trazem slug e nome; o id vem da lista do canal
+fn item_terms(tags: &[TagData], item: &WxrItem) -> Vec<TagData> {
+    item.terms
+        .iter()
+        .filter_map(|term| {
+            let taxonomy = Taxonomy::from_wp(&term.domain)?;
+            tags.iter()
+                .find(|tag| tag.taxonomy == taxonomy && tag.slug == term.slug)
+                .cloned()
+        })
+        .collect()
+}
+
+// Mesmos critérios da consulta do MySQL em posts
+fn wxr_posts(document: &WxrDocument, filter: &Filter) -> Vec<PostData> {
+    let authors = author_ids(document);
+    let tags = wxr_tags(document);
+    selected(document, "post", filter)
+        .filter(|item| !filter.require_image || item.content.contains("<img"))
+        .filter(|item| {
+            !filter.require_category || item.terms.iter().any(|term| term.domain == "category")
+        })
+        .map(|item| PostData {
+            id: item.post_id,
+            title: item.title.clone(),
+            slug: item.post_name.clone(),
+            html: item.content.clone(),
+            excerpt: item.excerpt.clone(),
+            created_at: item.post_date.clone(),
+            updated_at: item.post_modified.clone(),
+            status: PostStatus::from_wp(&item.status).unwrap_or_default(),
+            author_id: authors.get(item.creator.as_str()).cloned().unwrap_or_default(),
+            image_url: featured_image(document, item),
+            tags: vec![],
+            terms: item_terms(&tags, item),
+            guid: item.guid.clone(),
+        })
+        .collect()
+}
+
+fn wxr_pages(document: &WxrDocument, filter: &Filter) -> Vec<PageData> {
+    let authors = author_ids(document);
+    let mut pages: Vec<PageData> = selected(document, "page", filter)
+        .map(|item| PageData {
+            id: item.post_id,
+            title: item.title.clone(),
+            slug: item.post_name.clone(),
+            html: item.content.clone(),
+            excerpt: item.excerpt.clone(),
+            created_at: item.post_date.clone(),
+            updated_at: item.post_modified.clone(),
+            status: PostStatus::from_wp(&item.status).unwrap_or_default(),
+            author_id: authors.get(item.creator.as_str()).cloned().unwrap_or_default(),
+            image_url: featured_image(document, item),
+            parent_id: (item.post_parent != 0).then(|| item.post_parent.to_string()),
+            menu_order: item.menu_order,
+        })
+        .collect();
+    // Mesma ordem da consulta do MySQL: post_parent, menu_order
+    pages.sort_by_key(|page| {
+        let parent: u64 = page.parent_id.as_deref().and_then(|id| id.parse().ok()).unwrap_or(0);
+        (parent, page.menu_order)
+    });
+    pages
+}
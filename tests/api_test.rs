@@ -0,0 +1,203 @@
+use killer::api::{ApiSink, OnConflict};
+use killer::http::{build_client, RetryConfig};
+use killer::media::MediaFile;
+use killer::posts::PostData;
+use killer::throttle::Throttle;
+use killer::Sink;
+use mockito::Matcher;
+use std::time::{Duration, Instant};
+
+fn post() -> PostData {
+    serde_json::from_value(serde_json::json!({
+        "id": 10,
+        "title": "Greve",
+        "slug": "greve",
+        "html": "<p>Texto</p>",
+        "excerpt": "",
+        "created_at": "2024-01-01 10:00:00",
+        "updated_at": "2024-01-02 10:00:00",
+        "status": "published",
+        "author_id": "a1"
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_api_sink_creates_and_updates_posts() {
+    let mut server = mockito::Server::new_async().await;
+    let lookup = server
+        .mock("GET", "/posts")
+        .match_query(Matcher::UrlEncoded("slug".into(), "greve".into()))
+        .with_body("[]")
+        .create_async()
+        .await;
+    let create = server
+        .mock("POST", "/posts")
+        .match_header("authorization", "Bearer token")
+        .match_body(Matcher::PartialJson(serde_json::json!({"slug": "greve"})))
+        .with_body(r#"{"id": 42}"#)
+        .create_async()
+        .await;
+    let update = server
+        .mock("PUT", "/posts/abc")
+        .with_body(r#"{"id": "abc"}"#)
+        .create_async()
+        .await;
+
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let sink = ApiSink::new(client, server.url(), String::from("token"));
+
+    assert_eq!(sink.send_post(&post()).await.unwrap(), Some(String::from("42")));
+    assert_eq!(sink.update_post("abc", &post()).await.unwrap(), Some(String::from("abc")));
+    lookup.assert_async().await;
+    create.assert_async().await;
+    update.assert_async().await;
+}
+
+async fn existing_post(server: &mut mockito::ServerGuard) -> mockito::Mock {
+    server
+        .mock("GET", "/posts")
+        .match_query(Matcher::UrlEncoded("slug".into(), "greve".into()))
+        .with_body(r#"{"data": [{"id": "abc", "slug": "greve"}]}"#)
+        .create_async()
+        .await
+}
+
+#[tokio::test]
+async fn test_api_sink_upserts_existing_post() {
+    let mut server = mockito::Server::new_async().await;
+    existing_post(&mut server).await;
+    let update = server
+        .mock("PUT", "/posts/abc")
+        .with_body(r#"{"id": "abc"}"#)
+        .create_async()
+        .await;
+    let create = server.mock("POST", "/posts").expect(0).create_async().await;
+
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let sink = ApiSink::new(client, server.url(), String::from("token"));
+
+    assert_eq!(sink.send_post(&post()).await.unwrap(), Some(String::from("abc")));
+    update.assert_async().await;
+    create.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_sink_creates_when_lookup_is_not_supported() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/posts")
+        .match_query(Matcher::Any)
+        .with_status(405)
+        .create_async()
+        .await;
+    let create = server
+        .mock("POST", "/posts")
+        .with_body(r#"{"id": 42}"#)
+        .create_async()
+        .await;
+
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let sink = ApiSink::new(client, server.url(), String::from("token"));
+
+    assert_eq!(sink.send_post(&post()).await.unwrap(), Some(String::from("42")));
+    create.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_sink_on_conflict_skip_and_fail() {
+    let mut server = mockito::Server::new_async().await;
+    existing_post(&mut server).await;
+    let writes = server
+        .mock("PUT", Matcher::Any)
+        .expect(0)
+        .create_async()
+        .await;
+
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let skip = ApiSink::new(client.clone(), server.url(), String::from("token"))
+        .with_on_conflict(OnConflict::Skip);
+    assert_eq!(skip.send_post(&post()).await.unwrap(), Some(String::from("abc")));
+
+    let fail = ApiSink::new(client, server.url(), String::from("token"))
+        .with_on_conflict(OnConflict::Fail);
+    assert!(fail.send_post(&post()).await.is_err());
+    writes.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_sink_uploads_media_as_base64() {
+    let mut server = mockito::Server::new_async().await;
+    let upload = server
+        .mock("POST", "/images")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "path": "2024/01/capa.jpg",
+            "file_name": "capa.jpg",
+            "content_type": "image/jpeg",
+            "base64": "anBlZw=="
+        })))
+        .with_body(r#"{"url": "https://cdn.test/2024/01/capa.jpg"}"#)
+        .create_async()
+        .await;
+
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let sink = ApiSink::new(client, server.url(), String::from("token"));
+    let media = MediaFile::new("2024/01/capa.jpg", b"jpeg");
+
+    let url = sink.upload_media(&media).await.unwrap();
+    assert_eq!(url.as_deref(), Some("https://cdn.test/2024/01/capa.jpg"));
+    upload.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_sink_ignores_lookup_without_matching_slug() {
+    let mut server = mockito::Server::new_async().await;
+    // API que ignora `?slug=` e devolve a listagem normal
+    server
+        .mock("GET", "/posts")
+        .match_query(Matcher::Any)
+        .with_body(r#"[{"id": "other", "slug": "outro-post"}, {"id": "nope"}]"#)
+        .create_async()
+        .await;
+    let update = server.mock("PUT", Matcher::Any).expect(0).create_async().await;
+    let create = server
+        .mock("POST", "/posts")
+        .with_body(r#"{"id": 43}"#)
+        .create_async()
+        .await;
+
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let sink = ApiSink::new(client, server.url(), String::from("token"));
+
+    assert_eq!(sink.send_post(&post()).await.unwrap(), Some(String::from("43")));
+    update.assert_async().await;
+    create.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_sink_rate_limits_each_request() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/posts")
+        .match_query(Matcher::Any)
+        .with_body("[]")
+        .expect(3)
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/posts")
+        .with_body(r#"{"id": 42}"#)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let sink = ApiSink::new(client, server.url(), String::from("token")).with_throttle(Throttle::new(8, Some(20.0)));
+    let start = Instant::now();
+    for _ in 0..3 {
+        sink.send_post(&post()).await.unwrap();
+    }
+
+    // Busca e envio contam separado: 6 requisições a 20 req/s, uma a cada 50ms
+    assert!(start.elapsed() >= Duration::from_millis(250));
+}
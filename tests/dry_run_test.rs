@@ -0,0 +1,21 @@
+use killer::dry_run::DryRun;
+use killer::mapping::Entity;
+use std::env;
+use std::fs;
+
+#[test]
+fn test_dry_run_writes_payload_per_entity() {
+    let dir = env::temp_dir().join(format!("killer_dry_run_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let dry_run = DryRun::new(Some(dir.clone()));
+    dry_run
+        .write(Entity::Posts, 42, &serde_json::json!({"title": "Teste"}))
+        .unwrap();
+
+    let content = fs::read_to_string(dir.join("posts").join("42.json")).unwrap();
+    let payload: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(payload["title"], "Teste");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,30 @@
+use chrono::NaiveDate;
+use killer::filter::Filter;
+
+#[test]
+fn test_filter_date_range_includes_until_day() {
+    let filter = Filter {
+        since: NaiveDate::from_ymd_opt(2023, 1, 1),
+        until: NaiveDate::from_ymd_opt(2023, 1, 31),
+        ..Filter::default()
+    };
+
+    assert_eq!(filter.since_bound().as_deref(), Some("2023-01-01 00:00:00"));
+    assert_eq!(filter.until_bound().as_deref(), Some("2023-02-01 00:00:00"));
+    assert!(filter.accepts_date("2023-01-01 00:00:00"));
+    assert!(filter.accepts_date("2023-01-31 23:59:59"));
+    assert!(!filter.accepts_date("2022-12-31 23:59:59"));
+    assert!(!filter.accepts_date("2023-02-01 00:00:00"));
+}
+
+#[test]
+fn test_filter_ids() {
+    assert!(Filter::default().accepts_id(7));
+
+    let filter = Filter {
+        ids: vec![123, 456],
+        ..Filter::default()
+    };
+    assert!(filter.accepts_id(456));
+    assert!(!filter.accepts_id(7));
+}
@@ -0,0 +1,76 @@
+use killer::ghost::{ghost_role, to_ghost_date, GhostData, GhostPost, GhostUser};
+
+fn post(id: &str) -> GhostPost {
+    GhostPost {
+        id: id.to_string(),
+        title: String::from("Título"),
+        slug: String::from("titulo"),
+        html: String::from("<p>texto</p>"),
+        custom_excerpt: None,
+        feature_image: None,
+        post_type: String::from("post"),
+        status: String::from("published"),
+        created_at: to_ghost_date("2021-05-03 10:00:00"),
+        updated_at: to_ghost_date("2021-05-03 10:00:00"),
+        published_at: None,
+    }
+}
+
+#[test]
+fn test_to_ghost_date() {
+    assert_eq!(to_ghost_date("2021-05-03 10:20:30"), "2021-05-03T10:20:30.000Z");
+    assert_eq!(to_ghost_date("0000-00-00 00:00:00"), "0000-00-00 00:00:00");
+}
+
+#[test]
+fn test_ghost_import_layout() {
+    let mut data = GhostData::default();
+    data.add_post(post("10"), Some(String::from("3")), vec![String::from("7"), String::from("8")]);
+
+    let json = serde_json::to_value(data.into_import()).unwrap();
+    let data = &json["db"][0]["data"];
+    assert_eq!(data["posts"][0]["id"], "10");
+    assert_eq!(data["posts"][0]["type"], "post");
+    assert_eq!(data["posts_authors"][0]["author_id"], "3");
+    assert_eq!(data["posts_tags"][1]["tag_id"], "8");
+    assert_eq!(data["posts_tags"][1]["sort_order"], 1);
+    assert!(json["db"][0]["meta"]["exported_on"].is_i64());
+}
+
+fn user(id: &str) -> GhostUser {
+    GhostUser {
+        id: id.to_string(),
+        name: String::from("Maria"),
+        slug: String::from("maria"),
+        email: format!("{}@blog.com", id),
+        profile_image: None,
+        created_at: to_ghost_date("2021-05-03 10:00:00"),
+    }
+}
+
+#[test]
+fn test_ghost_roles_from_wordpress() {
+    assert_eq!(ghost_role(Some("administrator")), "Administrator");
+    assert_eq!(ghost_role(Some("editor")), "Editor");
+    assert_eq!(ghost_role(Some("contributor")), "Contributor");
+    assert_eq!(ghost_role(Some("author")), "Author");
+    assert_eq!(ghost_role(Some("translator")), "Author");
+    assert_eq!(ghost_role(None), "Author");
+}
+
+#[test]
+fn test_ghost_users_share_roles() {
+    let mut data = GhostData::default();
+    data.add_user(user("1"), "Administrator");
+    data.add_user(user("2"), "Author");
+    data.add_user(user("3"), "Author");
+
+    let json = serde_json::to_value(data.into_import()).unwrap();
+    let data = &json["db"][0]["data"];
+    assert_eq!(data["users"].as_array().unwrap().len(), 3);
+    assert_eq!(data["roles"], serde_json::json!([
+        {"id": "administrator", "name": "Administrator"},
+        {"id": "author", "name": "Author"}
+    ]));
+    assert_eq!(data["roles_users"][2], serde_json::json!({"role_id": "author", "user_id": "3"}));
+}
@@ -0,0 +1,54 @@
+use killer::http::{build_client, RetryConfig};
+use std::time::Duration;
+
+fn fast_retry() -> RetryConfig {
+    RetryConfig {
+        max_retries: 2,
+        min_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+    }
+}
+
+#[tokio::test]
+async fn test_client_retries_server_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/posts")
+        .with_status(502)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let client = build_client(&fast_retry()).unwrap();
+    let response = client
+        .post(format!("{}/posts", server.url()))
+        .json(&serde_json::json!({"title": "teste"}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 502);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_client_does_not_retry_validation_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/posts")
+        .with_status(422)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = build_client(&fast_retry()).unwrap();
+    let response = client
+        .post(format!("{}/posts", server.url()))
+        .json(&serde_json::json!({"title": "teste"}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 422);
+    mock.assert_async().await;
+}
@@ -0,0 +1,83 @@
+use killer::links::{Link, LinkRewriter};
+use killer::posts::{prepare_post_with_links, HtmlOptions, PostData};
+use killer::tags::TagMapping;
+
+fn rewriter() -> LinkRewriter {
+    let mut links = LinkRewriter::new("/{slug}/");
+    links.add(12, "greve-geral", "https://www.blog.org/?p=12");
+    links.add(30, "quem-somos", "");
+    links
+}
+
+#[test]
+fn test_resolve_by_slug_id_and_guid() {
+    let links = rewriter();
+    assert_eq!(links.resolve("https://blog.org/2021/05/greve-geral/"), Link::Resolved(String::from("/greve-geral/")));
+    assert_eq!(links.resolve("/?page_id=30"), Link::Resolved(String::from("/quem-somos/")));
+    assert_eq!(links.resolve("https://www.blog.org/?p=12"), Link::Resolved(String::from("/greve-geral/")));
+    assert_eq!(links.resolve("http://blog.org/greve-geral/#atos"), Link::Resolved(String::from("/greve-geral/#atos")));
+}
+
+#[test]
+fn test_resolve_keeps_external_media_and_home() {
+    let links = rewriter();
+    assert_eq!(links.resolve("https://outro.org/greve-geral/"), Link::Keep);
+    assert_eq!(links.resolve("https://blog.org/wp-content/uploads/2024/01/a.pdf"), Link::Keep);
+    assert_eq!(links.resolve("https://blog.org/"), Link::Keep);
+    assert_eq!(links.resolve("#topo"), Link::Keep);
+}
+
+#[test]
+fn test_rewrite_html_reports_unresolved_links() {
+    let links = rewriter();
+    let html = r#"<a href="https://blog.org/?p=12&amp;preview=true">a</a> <a href="https://blog.org/2020/01/apagado/">b</a> <a href="/category/lutas/">c</a>"#;
+
+    let rewritten = links.rewrite_html(7, html);
+
+    assert_eq!(
+        rewritten,
+        r#"<a href="/greve-geral/">a</a> <a href="https://blog.org/2020/01/apagado/">b</a> <a href="/category/lutas/">c</a>"#
+    );
+    assert_eq!(
+        links.unresolved(),
+        vec![
+            (7, String::from("/category/lutas/")),
+            (7, String::from("https://blog.org/2020/01/apagado/"))
+        ]
+    );
+}
+
+#[test]
+fn test_add_host_marks_old_domain_as_internal() {
+    let mut links = rewriter();
+    assert_eq!(links.resolve("https://antigo.org/greve-geral/"), Link::Keep);
+    links.add_host("https://www.antigo.org/");
+    links.add_host("outro-antigo.org");
+    assert_eq!(links.resolve("https://antigo.org/greve-geral/"), Link::Resolved(String::from("/greve-geral/")));
+    assert_eq!(links.resolve("http://outro-antigo.org/?p=12"), Link::Resolved(String::from("/greve-geral/")));
+}
+
+#[test]
+fn test_links_added_by_the_pipeline_are_not_reported() {
+    let links = rewriter();
+    let post: PostData = serde_json::from_value(serde_json::json!({
+        "id": 40,
+        "title": "Ato",
+        "slug": "ato",
+        "html": "Um\n\nDois <a href=\"https://blog.org/greve-geral/\">greve</a>\n\nTrês\n\n<h4>Edital</h4>Baixe o <a href=\"https://blog.org/wp-content/uploads/2024/01/edital.pdf\">edital</a>",
+        "excerpt": "",
+        "created_at": "2024-01-01 10:00:00",
+        "updated_at": "2024-01-01 10:00:00",
+        "status": "published",
+        "author_id": "1",
+        "image_url": null
+    }))
+    .unwrap();
+
+    let prepared = prepare_post_with_links(post, &TagMapping::default(), &HtmlOptions::default(), Some(&links));
+
+    assert!(prepared.html.contains(r#"href="/greve-geral/""#));
+    assert!(prepared.html.contains(r#"href="/nos-apoie""#));
+    assert!(prepared.html.contains(r#"href="/content/images/2024/01/edital.pdf""#));
+    assert!(links.unresolved().is_empty());
+}
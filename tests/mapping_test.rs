@@ -0,0 +1,83 @@
+use killer::mapping::{Entity, IdMap};
+use std::env;
+use std::fs;
+
+#[test]
+fn test_id_map_persists_between_loads() {
+    let path = env::temp_dir().join(format!("killer_mapping_{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let id_map = IdMap::load(&path).unwrap();
+    assert!(!id_map.contains(Entity::Posts, 10));
+    id_map.insert(Entity::Posts, 10, "abc").unwrap();
+    id_map.insert(Entity::Authors, 10, "def").unwrap();
+
+    let reloaded = IdMap::load(&path).unwrap();
+    assert_eq!(reloaded.get(Entity::Posts, 10), Some(String::from("abc")));
+    assert_eq!(reloaded.get(Entity::Authors, "10"), Some(String::from("def")));
+    assert_eq!(reloaded.get(Entity::Tags, 10), None);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_id_map_retain_pending_skips_migrated() {
+    let path = env::temp_dir().join(format!("killer_mapping_resume_{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+
+    let id_map = IdMap::load(&path).unwrap();
+    id_map.insert(Entity::Tags, 2, "remote-2").unwrap();
+    let mut items = vec![1, 2, 3];
+    let skipped = id_map.retain_pending(Entity::Tags, &mut items, |id| *id);
+
+    assert_eq!(skipped, 1);
+    assert_eq!(items, vec![1, 3]);
+    assert_eq!(id_map.count(Entity::Tags), 1);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_id_map_appends_changes_and_compacts_on_load() {
+    let path = env::temp_dir().join(format!("killer_mapping_journal_{}.json", std::process::id()));
+    let journal = env::temp_dir().join(format!("killer_mapping_journal_{}.json.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let _ = fs::remove_file(&journal);
+
+    let id_map = IdMap::load(&path).unwrap();
+    assert!(!path.exists());
+    id_map.insert(Entity::Posts, 1, "a").unwrap();
+    let snapshot = fs::read_to_string(&path).unwrap();
+    id_map.insert(Entity::Posts, 2, "b").unwrap();
+    id_map.remove(Entity::Posts, 1).unwrap();
+
+    // Só o journal cresce; o JSON fica como estava até a próxima carga
+    assert_eq!(fs::read_to_string(&path).unwrap(), snapshot);
+    assert_eq!(fs::read_to_string(&journal).unwrap().lines().count(), 3);
+
+    let reloaded = IdMap::load(&path).unwrap();
+    assert_eq!(reloaded.get(Entity::Posts, 1), None);
+    assert_eq!(reloaded.get(Entity::Posts, 2), Some(String::from("b")));
+    assert!(!journal.exists());
+    assert!(fs::read_to_string(&path).unwrap().contains("\"2\": \"b\""));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_id_map_refuses_another_blog() {
+    let path = env::temp_dir().join(format!("killer_mapping_blog_{}.json", std::process::id()));
+    // Arquivo de antes do blog_id: só entidades
+    fs::write(&path, r#"{"posts": {"10": "abc"}}"#).unwrap();
+
+    let id_map = IdMap::load(&path).unwrap();
+    assert_eq!(id_map.get(Entity::Posts, 10), Some(String::from("abc")));
+    id_map.bind_blog(2).unwrap();
+
+    let reloaded = IdMap::load(&path).unwrap();
+    reloaded.bind_blog(2).unwrap();
+    assert!(reloaded.bind_blog(3).is_err());
+    assert_eq!(reloaded.get(Entity::Posts, 10), Some(String::from("abc")));
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,63 @@
+use killer::http::{build_client, RetryConfig};
+use killer::media::{content_type, copy_uploads, referenced_uploads, MediaUploader};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+
+#[test]
+fn test_content_type_by_extension() {
+    assert_eq!(content_type("capa.JPG"), "image/jpeg");
+    assert_eq!(content_type("edital.pdf"), "application/pdf");
+    assert_eq!(content_type("arquivo"), "application/octet-stream");
+}
+
+#[test]
+fn test_referenced_uploads_from_html_and_featured_image() {
+    let html = r#"<img src="https://blog.com/wp-content/uploads/2024/01/a.jpg"><a href="/wp-content/uploads/2024/02/b.pdf?v=1">pdf</a>"#;
+    let paths = referenced_uploads(html, Some("2024/03/capa.jpg"));
+    assert_eq!(paths, vec!["2024/01/a.jpg", "2024/02/b.pdf", "2024/03/capa.jpg"]);
+}
+
+#[test]
+fn test_copy_uploads_keeps_layout_and_skips_unreferenced() {
+    let root = env::temp_dir().join(format!("killer_copy_{}", std::process::id()));
+    let uploads = root.join("uploads");
+    let images = root.join("content/images");
+    fs::create_dir_all(uploads.join("2024/01")).unwrap();
+    fs::write(uploads.join("2024/01/a.jpg"), b"a").unwrap();
+    fs::write(uploads.join("2024/01/b.jpg"), b"b").unwrap();
+
+    let referenced: HashSet<String> = [String::from("2024/01/a.jpg")].into();
+    let manifest = copy_uploads(&uploads, &images, Some(&referenced)).unwrap();
+
+    assert_eq!(
+        manifest.get("/wp-content/uploads/2024/01/a.jpg").map(String::as_str),
+        Some("/content/images/2024/01/a.jpg")
+    );
+    assert_eq!(manifest.len(), 1);
+    assert_eq!(fs::read(images.join("2024/01/a.jpg")).unwrap(), b"a");
+    assert!(!images.join("2024/01/b.jpg").exists());
+
+    let manifest = copy_uploads(&uploads, &images, None).unwrap();
+    assert_eq!(manifest.len(), 2);
+    let _ = fs::remove_dir_all(root);
+}
+
+#[tokio::test]
+async fn test_fetch_refuses_paths_outside_uploads_dir() {
+    let root = env::temp_dir().join(format!("killer_fetch_{}", std::process::id()));
+    let uploads = root.join("uploads");
+    fs::create_dir_all(uploads.join("2024/01")).unwrap();
+    fs::write(uploads.join("2024/01/a.jpg"), b"a").unwrap();
+    fs::write(root.join("segredo.txt"), b"senha").unwrap();
+
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let uploader = MediaUploader::new(client, Some(uploads.clone()), None);
+
+    assert_eq!(uploader.fetch("2024/01/a.jpg").await.unwrap(), b"a");
+    assert!(uploader.fetch("../segredo.txt").await.is_err());
+    assert!(uploader.fetch("2024/../../segredo.txt").await.is_err());
+    let absolute = root.join("segredo.txt");
+    assert!(uploader.fetch(absolute.to_str().unwrap()).await.is_err());
+    let _ = fs::remove_dir_all(root);
+}
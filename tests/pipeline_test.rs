@@ -0,0 +1,599 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use killer::authors::{migrate_authors, AuthorPost, ProfileImage};
+use killer::filter::{Filter, PostStatus};
+use killer::http::{build_client, RetryConfig};
+use killer::mapping::{Entity, IdMap};
+use killer::media::{MediaFile, MediaUploader};
+use killer::migrate::MigrateOptions;
+use killer::pages::{migrate_pages, PageData};
+use killer::posts::{migrate_posts, PostData};
+use killer::prune::{check_missing, find_prunable, prune, PruneAction, PruneReason};
+use killer::redirects::{build_redirects, Permalinks, Redirect, RedirectOptions};
+use killer::sync::{sync, SyncState};
+use killer::tags::{migrate_tags, TagData, Taxonomy};
+use killer::{Sink, Source};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Default)]
+struct MemorySource {
+    authors: Vec<AuthorPost>,
+    tags: Vec<TagData>,
+    posts: Vec<PostData>,
+    pages: Vec<PageData>,
+    trashed: Vec<u64>,
+    permalinks: Option<Permalinks>,
+}
+
+#[async_trait]
+impl Source for MemorySource {
+    async fn authors(&self, _filter: &Filter) -> Result<Vec<AuthorPost>> {
+        Ok(self.authors.clone())
+    }
+
+    async fn tags(&self) -> Result<Vec<TagData>> {
+        Ok(self.tags.clone())
+    }
+
+    async fn posts(&self, filter: &Filter) -> Result<Vec<PostData>> {
+        Ok(self
+            .posts
+            .iter()
+            .filter(|post| filter.accepts_modified(&post.updated_at))
+            .cloned()
+            .collect())
+    }
+
+    async fn pages(&self, _filter: &Filter) -> Result<Vec<PageData>> {
+        Ok(self.pages.clone())
+    }
+
+    async fn permalinks(&self) -> Result<Option<Permalinks>> {
+        Ok(self.permalinks.clone())
+    }
+
+    async fn statuses(&self, ids: &[u64]) -> Result<HashMap<u64, String>> {
+        let posts = self.posts.iter().map(|post| (post.id, post.status.as_wp()));
+        let pages = self.pages.iter().map(|page| (page.id, page.status.as_wp()));
+        let trashed = self.trashed.iter().map(|id| (*id, "trash"));
+        Ok(posts
+            .chain(pages)
+            .chain(trashed)
+            .filter(|(id, _)| ids.contains(id))
+            .map(|(id, status)| (id, status.to_string()))
+            .collect())
+    }
+}
+
+#[derive(Default)]
+struct RecordingSink {
+    tags: Mutex<Vec<TagData>>,
+    posts: Mutex<Vec<PostData>>,
+    pages: Mutex<Vec<PageData>>,
+    updated: Mutex<Vec<String>>,
+    pruned: Mutex<Vec<String>>,
+    uploads: Mutex<Vec<String>>,
+    profile_images: Mutex<Vec<(String, String, String)>>,
+    upload_delay: Duration,
+}
+
+#[async_trait]
+impl Sink for RecordingSink {
+    async fn send_author(&self, author: &AuthorPost) -> Result<Option<String>> {
+        Ok(Some(format!("remote-author-{}", author.id)))
+    }
+
+    async fn send_tag(&self, tag: &TagData) -> Result<Option<String>> {
+        self.tags.lock().unwrap().push(tag.clone());
+        Ok(Some(format!("remote-{}", tag.id)))
+    }
+
+    async fn send_post(&self, post: &PostData) -> Result<Option<String>> {
+        self.posts.lock().unwrap().push(post.clone());
+        Ok(Some(format!("remote-{}", post.id)))
+    }
+
+    async fn send_page(&self, page: &PageData) -> Result<Option<String>> {
+        self.pages.lock().unwrap().push(page.clone());
+        Ok(Some(format!("remote-{}", page.id)))
+    }
+
+    async fn update_post(&self, remote_id: &str, _post: &PostData) -> Result<Option<String>> {
+        self.updated.lock().unwrap().push(remote_id.to_string());
+        Ok(Some(remote_id.to_string()))
+    }
+
+    async fn update_page(&self, remote_id: &str, _page: &PageData) -> Result<Option<String>> {
+        self.updated.lock().unwrap().push(remote_id.to_string());
+        Ok(Some(remote_id.to_string()))
+    }
+
+    async fn delete(&self, _entity: Entity, remote_id: &str) -> Result<Option<String>> {
+        self.pruned.lock().unwrap().push(format!("delete {}", remote_id));
+        Ok(Some(remote_id.to_string()))
+    }
+
+    async fn unpublish(&self, _entity: Entity, remote_id: &str) -> Result<Option<String>> {
+        self.pruned.lock().unwrap().push(format!("unpublish {}", remote_id));
+        Ok(Some(remote_id.to_string()))
+    }
+
+    async fn send_profile_image(&self, image: &ProfileImage) -> Result<Option<String>> {
+        let image = (image.author_id.clone(), image.path_image.clone(), image.base64.clone());
+        self.profile_images.lock().unwrap().push(image);
+        Ok(Some(String::from("profile-image")))
+    }
+
+    async fn upload_media(&self, media: &MediaFile) -> Result<Option<String>> {
+        tokio::time::sleep(self.upload_delay).await;
+        self.uploads.lock().unwrap().push(media.path.clone());
+        Ok(Some(format!("https://cdn.test/{}", media.path)))
+    }
+}
+
+fn post(id: u64) -> PostData {
+    PostData {
+        id,
+        title: format!("Post {}", id),
+        slug: format!("post-{}", id),
+        html: String::from("Primeiro\n\n<script>alert(1)</script>Segundo"),
+        excerpt: String::new(),
+        created_at: String::from("2024-01-01 10:00:00"),
+        updated_at: String::from("2024-01-02 10:00:00"),
+        status: PostStatus::Publish,
+        author_id: String::from("1"),
+        image_url: Some(String::from("https://blog.com/wp-content/uploads/2024/01/capa.jpg")),
+        tags: vec![],
+        terms: vec![],
+        guid: format!("https://blog.com/?p={}", id),
+    }
+}
+
+fn page(id: u64, parent_id: Option<&str>) -> PageData {
+    PageData {
+        id,
+        title: format!("Página {}", id),
+        slug: format!("pagina-{}", id),
+        html: String::from("Conteúdo"),
+        excerpt: String::new(),
+        created_at: String::from("2024-01-01 10:00:00"),
+        updated_at: String::from("2024-01-01 10:00:00"),
+        status: PostStatus::Draft,
+        author_id: String::from("1"),
+        image_url: None,
+        parent_id: parent_id.map(String::from),
+        menu_order: 0,
+    }
+}
+
+fn temp_map(name: &str) -> (std::path::PathBuf, Arc<IdMap>) {
+    let path = env::temp_dir().join(format!("killer_pipeline_{}_{}.json", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    let id_map = Arc::new(IdMap::load(&path).unwrap());
+    (path, id_map)
+}
+
+#[tokio::test]
+async fn test_migrate_posts_sanitizes_and_records_mapping() {
+    let (path, id_map) = temp_map("posts");
+    id_map.insert(Entity::Authors, 1, "author-remote").unwrap();
+    id_map.insert(Entity::Posts, 2, "remote-2").unwrap();
+    id_map.insert(Entity::Tags, 5, "tag-remote").unwrap();
+
+    let mut first = post(1);
+    first.terms = vec![
+        TagData {
+            id: 9,
+            name: String::from("Greve, geral"),
+            slug: String::from("greve-geral"),
+            description: String::new(),
+            count: 1,
+            parent_id: None,
+            taxonomy: Taxonomy::PostTag,
+        },
+        TagData {
+            id: 5,
+            name: String::from("Nacional"),
+            slug: String::from("nacional"),
+            description: String::new(),
+            count: 1,
+            parent_id: None,
+            taxonomy: Taxonomy::Category,
+        },
+    ];
+    let source = MemorySource {
+        posts: vec![first, post(2)],
+        ..MemorySource::default()
+    };
+    let sink = Arc::new(RecordingSink::default());
+    let options = MigrateOptions {
+        resume: true,
+        ..MigrateOptions::default()
+    };
+    migrate_posts(&source, sink.clone(), id_map.clone(), &options).await;
+
+    let sent = sink.posts.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].id, 1);
+    assert_eq!(sent[0].author_id, "author-remote");
+    assert!(!sent[0].html.contains("<script>"));
+    assert_eq!(
+        sent[0].image_url.as_deref(),
+        Some("/content/images/2024/01/capa.jpg")
+    );
+    assert_eq!(id_map.get(Entity::Posts, 1), Some(String::from("remote-1")));
+
+    let payload = serde_json::to_value(&sent[0]).unwrap();
+    assert_eq!(
+        payload["tags"],
+        serde_json::json!([
+            {"id": "tag-remote", "slug": "nacional", "name": "Nacional"},
+            {"slug": "greve-geral", "name": "Greve, geral"}
+        ])
+    );
+    assert!(payload.get("terms").is_none());
+    assert_eq!(payload["status"], "published");
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_migrate_pages_sends_parent_before_child() {
+    let (path, id_map) = temp_map("pages");
+
+    let source = MemorySource {
+        pages: vec![page(11, Some("10")), page(10, None)],
+        ..MemorySource::default()
+    };
+    let sink = Arc::new(RecordingSink::default());
+    migrate_pages(&source, sink.clone(), id_map.clone(), &MigrateOptions::default()).await;
+
+    let sent = sink.pages.lock().unwrap();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].id, 10);
+    assert_eq!(sent[1].id, 11);
+    assert_eq!(sent[1].parent_id.as_deref(), Some("remote-10"));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_migrate_pages_leaves_out_unmapped_author_and_parent() {
+    let (path, id_map) = temp_map("pages_unmapped");
+
+    // Pai fora da seleção e autor ainda não migrado
+    let source = MemorySource {
+        pages: vec![page(21, Some("20"))],
+        ..MemorySource::default()
+    };
+    let sink = Arc::new(RecordingSink::default());
+    migrate_pages(&source, sink.clone(), id_map.clone(), &MigrateOptions::default()).await;
+
+    let sent = sink.pages.lock().unwrap();
+    assert_eq!(sent[0].parent_id, None);
+    let payload = serde_json::to_value(&sent[0]).unwrap();
+    assert!(payload.get("author_id").is_none());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_migrate_tags_sends_parent_category_first() {
+    let (path, id_map) = temp_map("tags");
+    let category = |id: i32, name: &str, parent_id: Option<&str>| TagData {
+        id,
+        name: name.to_string(),
+        slug: name.to_lowercase(),
+        description: String::new(),
+        count: 0,
+        parent_id: parent_id.map(String::from),
+        taxonomy: Taxonomy::Category,
+    };
+
+    let source = MemorySource {
+        tags: vec![category(2, "Europa", Some("1")), category(1, "Internacional", None)],
+        ..MemorySource::default()
+    };
+    let sink = Arc::new(RecordingSink::default());
+    migrate_tags(&source, sink.clone(), id_map.clone(), &MigrateOptions::default()).await;
+
+    let sent = sink.tags.lock().unwrap();
+    assert_eq!(sent[0].id, 1);
+    assert_eq!(sent[1].parent_id.as_deref(), Some("remote-1"));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_migrate_tags_sends_unmapped_parent_at_root() {
+    let (path, id_map) = temp_map("tags_orphan");
+
+    // Pai que não está na origem não vai como id do WordPress
+    let source = MemorySource {
+        tags: vec![TagData {
+            id: 3,
+            name: String::from("Asia"),
+            slug: String::from("asia"),
+            description: String::new(),
+            count: 0,
+            parent_id: Some(String::from("99")),
+            taxonomy: Taxonomy::Category,
+        }],
+        ..MemorySource::default()
+    };
+    let sink = Arc::new(RecordingSink::default());
+    migrate_tags(&source, sink.clone(), id_map.clone(), &MigrateOptions::default()).await;
+
+    assert_eq!(sink.tags.lock().unwrap()[0].parent_id, None);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_sync_updates_migrated_and_creates_new_posts() {
+    let (path, id_map) = temp_map("sync");
+    let state_path = env::temp_dir().join(format!("killer_sync_{}.json", std::process::id()));
+    let _ = fs::remove_file(&state_path);
+    id_map.insert(Entity::Posts, 1, "remote-old").unwrap();
+
+    let mut edited = post(2);
+    edited.updated_at = String::from("2024-03-01 09:00:00");
+    let source = MemorySource {
+        posts: vec![post(1), edited],
+        ..MemorySource::default()
+    };
+
+    let sink = Arc::new(RecordingSink::default());
+    let mut state = SyncState::load(&state_path).unwrap();
+    let (posts, _) = sync(&source, sink.clone(), id_map.clone(), &mut state, &MigrateOptions::default()).await;
+    assert_eq!(posts.sent, 2);
+    assert_eq!(*sink.updated.lock().unwrap(), vec![String::from("remote-old")]);
+    assert_eq!(sink.posts.lock().unwrap().len(), 1);
+    assert_eq!(state.get(Entity::Posts).as_deref(), Some("2024-03-01 09:00:00"));
+    state.save().unwrap();
+
+    // Na segunda vez só o post alterado depois da marca é enviado, como atualização
+    let sink = Arc::new(RecordingSink::default());
+    let mut state = SyncState::load(&state_path).unwrap();
+    let (posts, _) = sync(&source, sink.clone(), id_map.clone(), &mut state, &MigrateOptions::default()).await;
+    assert_eq!(posts.sent, 1);
+    assert_eq!(*sink.updated.lock().unwrap(), vec![String::from("remote-2")]);
+    assert!(sink.posts.lock().unwrap().is_empty());
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&state_path).unwrap();
+}
+
+#[tokio::test]
+async fn test_prune_finds_trashed_and_missing_posts() {
+    let (path, id_map) = temp_map("prune");
+    id_map.insert(Entity::Posts, 1, "remote-1").unwrap();
+    id_map.insert(Entity::Posts, 2, "remote-2").unwrap();
+    id_map.insert(Entity::Posts, 3, "remote-3").unwrap();
+    id_map.insert(Entity::Pages, 10, "remote-10").unwrap();
+    let source = MemorySource {
+        posts: vec![post(1)],
+        pages: vec![page(10, None)],
+        trashed: vec![2],
+        ..Default::default()
+    };
+
+    let candidates = find_prunable(&source, &id_map).await.unwrap();
+    let found: Vec<(&str, PruneReason)> = candidates
+        .iter()
+        .map(|candidate| (candidate.wp_id.as_str(), candidate.reason))
+        .collect();
+    assert_eq!(found, vec![("2", PruneReason::Trashed), ("3", PruneReason::Missing)]);
+
+    // O WXR só traz o que foi exportado: os ausentes ficam de fora sem --allow-missing
+    let partial = check_missing(candidates.clone(), &id_map, true, false).unwrap();
+    assert_eq!(partial.len(), 1);
+    assert_eq!(partial[0].reason, PruneReason::Trashed);
+    assert_eq!(check_missing(candidates.clone(), &id_map, true, true).unwrap().len(), 2);
+    assert_eq!(check_missing(candidates.clone(), &id_map, false, false).unwrap().len(), 2);
+
+    let sink = RecordingSink::default();
+    let summary = prune(&sink, &id_map, &candidates, PruneAction::Unpublish).await;
+    assert_eq!(summary.sent, 2);
+    assert_eq!(*sink.pruned.lock().unwrap(), vec!["unpublish remote-2", "unpublish remote-3"]);
+    assert!(id_map.contains(Entity::Posts, 2));
+
+    prune(&sink, &id_map, &candidates, PruneAction::Delete).await;
+    assert!(!id_map.contains(Entity::Posts, 2));
+    assert!(!id_map.contains(Entity::Posts, 3));
+    assert!(id_map.contains(Entity::Posts, 1));
+    let _ = fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_migrate_posts_uploads_media_once() {
+    let (path, id_map) = temp_map("media");
+    let uploads_dir = env::temp_dir().join(format!("killer_uploads_{}", std::process::id()));
+    fs::create_dir_all(uploads_dir.join("2024/01")).unwrap();
+    fs::write(uploads_dir.join("2024/01/capa.jpg"), b"jpeg").unwrap();
+
+    let mut first = post(1);
+    first.html = String::from(r#"<img src="https://blog.com/wp-content/uploads/2024/01/capa.jpg" alt="">"#);
+    let source = MemorySource {
+        posts: vec![first, post(2)],
+        ..Default::default()
+    };
+    // Envio lento, para os dois posts pedirem a mesma capa ao mesmo tempo
+    let sink = Arc::new(RecordingSink {
+        upload_delay: Duration::from_millis(50),
+        ..RecordingSink::default()
+    });
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let options = MigrateOptions {
+        media: Some(Arc::new(MediaUploader::new(client, Some(uploads_dir.clone()), None))),
+        ..MigrateOptions::default()
+    };
+
+    let summary = migrate_posts(&source, sink.clone(), id_map, &options).await;
+
+    assert_eq!(summary.sent, 2);
+    assert_eq!(*sink.uploads.lock().unwrap(), vec!["2024/01/capa.jpg"]);
+    for post in sink.posts.lock().unwrap().iter() {
+        assert_eq!(post.image_url.as_deref(), Some("https://cdn.test/2024/01/capa.jpg"));
+        if post.id == 1 {
+            assert!(post.html.contains(r#"src="https://cdn.test/2024/01/capa.jpg""#));
+        }
+    }
+    let _ = fs::remove_dir_all(uploads_dir);
+    let _ = fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_migrate_authors_sends_profile_image_once() {
+    let (path, id_map) = temp_map("profile_image");
+    let uploads_dir = env::temp_dir().join(format!("killer_avatars_{}", std::process::id()));
+    fs::create_dir_all(uploads_dir.join("2024/01")).unwrap();
+    fs::write(uploads_dir.join("2024/01/foto.jpg"), b"jpeg").unwrap();
+
+    let source = MemorySource {
+        authors: vec![AuthorPost {
+            id: 7,
+            name: String::from("Maria"),
+            email: String::from("maria@blog.com"),
+            login: String::from("maria"),
+            nicename: String::from("maria"),
+            role: None,
+            password: String::new(),
+            created_at: String::from("2024-01-01 10:00:00"),
+            image_url: Some(String::from("https://blog.com/wp-content/uploads/2024/01/foto.jpg")),
+        }],
+        ..Default::default()
+    };
+    let sink = Arc::new(RecordingSink::default());
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let options = MigrateOptions {
+        media: Some(Arc::new(MediaUploader::new(client, Some(uploads_dir.clone()), None))),
+        ..MigrateOptions::default()
+    };
+
+    migrate_authors(&source, sink.clone(), id_map.clone(), &options).await;
+    // Segunda execução: o autor é atualizado, a foto não vai de novo
+    migrate_authors(&source, sink.clone(), id_map.clone(), &options).await;
+
+    assert_eq!(id_map.get(Entity::Authors, 7).as_deref(), Some("remote-author-7"));
+    assert_eq!(id_map.get(Entity::ProfileImages, 7).as_deref(), Some("2024/01/foto.jpg"));
+    assert_eq!(
+        *sink.profile_images.lock().unwrap(),
+        vec![(
+            String::from("remote-author-7"),
+            String::from("2024/01/foto.jpg"),
+            String::from("anBlZw==")
+        )]
+    );
+    let _ = fs::remove_dir_all(uploads_dir);
+    let _ = fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_build_redirects_includes_archives() {
+    let term = |id: i32, slug: &str, parent_id: Option<&str>, taxonomy: Taxonomy| TagData {
+        id,
+        name: slug.to_string(),
+        slug: slug.to_string(),
+        description: String::new(),
+        count: 1,
+        parent_id: parent_id.map(String::from),
+        taxonomy,
+    };
+    let source = MemorySource {
+        authors: vec![AuthorPost {
+            id: 1,
+            name: String::from("Maria"),
+            email: String::from("maria@blog.com"),
+            login: String::from("Maria.Silva"),
+            nicename: String::from("maria-silva"),
+            role: None,
+            password: String::new(),
+            created_at: String::from("2024-01-01 10:00:00"),
+            image_url: None,
+        }],
+        tags: vec![
+            term(1, "internacional", None, Taxonomy::Category),
+            term(2, "europa", Some("1"), Taxonomy::Category),
+            term(3, "greve", None, Taxonomy::PostTag),
+        ],
+        posts: vec![post(1)],
+        permalinks: Some(Permalinks {
+            structure: String::from("/%year%/%postname%/"),
+            category_base: String::from("/temas"),
+            tag_base: String::from("tema"),
+        }),
+        ..MemorySource::default()
+    };
+    let options = RedirectOptions {
+        tag_base: Some(String::from("assunto")),
+        tag_format: String::from("/temas/{slug}/"),
+        author_format: String::from("/autor/{slug}/"),
+        ..RedirectOptions::default()
+    };
+
+    let redirects = build_redirects(&source, &Filter::default(), &options).await.unwrap();
+
+    let redirect = |from: &str, to: &str| Redirect {
+        from: from.to_string(),
+        to: to.to_string(),
+    };
+    assert_eq!(
+        redirects,
+        vec![
+            redirect("/2024/post-1/", "/post-1/"),
+            redirect("/assunto/greve/", "/temas/greve/"),
+            redirect("/author/maria-silva/", "/autor/maria-silva/"),
+            redirect("/temas/internacional/", "/tag/internacional/"),
+            redirect("/temas/internacional/europa/", "/tag/europa/"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_prune_refuses_when_most_of_the_mapping_is_missing() {
+    let (path, id_map) = temp_map("prune_missing");
+    id_map.insert(Entity::Posts, 1, "remote-1").unwrap();
+    id_map.insert(Entity::Posts, 2, "remote-2").unwrap();
+    id_map.insert(Entity::Posts, 3, "remote-3").unwrap();
+    // Origem errada ou vazia: quase nada do mapeamento existe
+    let source = MemorySource {
+        posts: vec![post(1)],
+        ..Default::default()
+    };
+
+    let candidates = find_prunable(&source, &id_map).await.unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert!(check_missing(candidates.clone(), &id_map, false, false).is_err());
+    assert_eq!(check_missing(candidates, &id_map, false, true).unwrap().len(), 2);
+    let _ = fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_media_rewrite_html_replaces_whole_paths() {
+    let uploads_dir = env::temp_dir().join(format!("killer_rewrite_{}", std::process::id()));
+    fs::create_dir_all(uploads_dir.join("x")).unwrap();
+    fs::write(uploads_dir.join("x/a.jpg"), b"jpeg").unwrap();
+    fs::write(uploads_dir.join("x/a.jpg.webp"), b"webp").unwrap();
+    let client = build_client(&RetryConfig::default()).unwrap();
+    let media = MediaUploader::new(client, Some(uploads_dir.clone()), None);
+    let sink = RecordingSink::default();
+
+    let html = String::from(
+        r#"<img src="/content/images/x/a.jpg"><img src="/content/images/x/a.jpg.webp"><img src="/content/images/x/a.jpg?w=300">"#,
+    );
+    let rewritten = media.rewrite_html(&sink, html).await;
+
+    assert_eq!(
+        rewritten,
+        r#"<img src="https://cdn.test/x/a.jpg"><img src="https://cdn.test/x/a.jpg.webp"><img src="https://cdn.test/x/a.jpg?w=300">"#
+    );
+    let mut uploads = sink.uploads.lock().unwrap().clone();
+    uploads.sort();
+    assert_eq!(uploads, vec!["x/a.jpg", "x/a.jpg.webp"]);
+    let _ = fs::remove_dir_all(uploads_dir);
+}
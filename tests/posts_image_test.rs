@@ -1,3 +1,5 @@
+use killer::posts::{clean_html, collapse_size_variant, prepare_post, process_html, HtmlOptions, PostData};
+use killer::tags::TagMapping;
 use killer::process_image_url;
 
 #[test]
@@ -31,3 +33,58 @@ fn test_process_image_url_withould_domain() {
     let result = process_image_url(image_to_process);
     assert_eq!(result, image_processed);
 }
+
+const RESPONSIVE_IMG: &str = r#"<img class="aligncenter wp-image-12 size-large" src="https://blog.com/wp-content/uploads/2024/01/foto-1024x683.jpg" srcset="https://blog.com/wp-content/uploads/2024/01/foto-1024x683.jpg 1024w, https://blog.com/wp-content/uploads/2024/01/foto-300x200.jpg 300w" sizes="(max-width: 1024px) 100vw, 1024px" alt="Ato">"#;
+
+#[test]
+fn test_process_html_rewrites_srcset() {
+    let html = process_html(String::from(RESPONSIVE_IMG), &HtmlOptions::default());
+    assert_eq!(
+        html,
+        r#"<img class="aligncenter wp-image-12 size-large" src="/content/images/2024/01/foto-1024x683.jpg" srcset="/content/images/2024/01/foto-1024x683.jpg 1024w, /content/images/2024/01/foto-300x200.jpg 300w" sizes="(max-width: 1024px) 100vw, 1024px" alt="Ato">"#
+    );
+    let cleaned = clean_html(&html);
+    assert!(cleaned.contains("srcset="));
+    assert!(!cleaned.contains("wp-image-12"));
+}
+
+#[test]
+fn test_process_html_collapses_size_variants() {
+    let options = HtmlOptions {
+        collapse_sizes: true,
+        ..HtmlOptions::default()
+    };
+    let html = process_html(String::from(RESPONSIVE_IMG), &options);
+    assert_eq!(
+        html,
+        r#"<img class="aligncenter wp-image-12 size-large" src="/content/images/2024/01/foto.jpg" alt="Ato">"#
+    );
+}
+
+#[test]
+fn test_collapse_size_variant_keeps_other_names() {
+    assert_eq!(collapse_size_variant("/content/images/2024/01/foto-300x200.jpg"), "/content/images/2024/01/foto.jpg");
+    assert_eq!(collapse_size_variant("/content/images/2024/01/1920x1080.jpg"), "/content/images/2024/01/1920x1080.jpg");
+}
+
+#[test]
+fn test_prepare_post_keeps_caption_as_figure() {
+    let post: PostData = serde_json::from_value(serde_json::json!({
+        "id": 1,
+        "title": "Ato",
+        "slug": "ato",
+        "html": r#"[caption id="attachment_5"]<img class="size-medium wp-image-5" src="https://blog.com/wp-content/uploads/2020/01/a.jpg" alt="Alt"/> Legenda[/caption]"#,
+        "excerpt": "",
+        "created_at": "2020-01-01 10:00:00",
+        "updated_at": "2020-01-01 10:00:00",
+        "status": "published",
+        "author_id": "1"
+    }))
+    .unwrap();
+
+    let prepared = prepare_post(post, &TagMapping::default(), &HtmlOptions::default());
+
+    assert!(!prepared.html.contains("[caption"));
+    assert!(prepared.html.contains(r#"<img src="/content/images/2020/01/a.jpg" alt="Alt">"#));
+    assert!(prepared.html.contains("<figcaption>Legenda</figcaption>"));
+}
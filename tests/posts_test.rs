@@ -1,4 +1,5 @@
 use killer::text_to_html_paragraphs;
+#[allow(unused_imports)]
 use pretty_assertions::assert_eq;
 
 #[test]
@@ -0,0 +1,141 @@
+use killer::filter::PostStatus;
+use killer::pages::PageData;
+use killer::posts::PostData;
+use killer::authors::{nicename, AuthorPost};
+use killer::redirects::{ghost_yaml, htaccess, nginx_map, page_path, post_path, Permalinks, Redirect};
+use killer::tags::{TagData, Taxonomy};
+use std::collections::HashMap;
+
+fn post() -> PostData {
+    serde_json::from_value(serde_json::json!({
+        "id": 12,
+        "title": "Greve geral",
+        "slug": "greve-geral",
+        "html": "",
+        "excerpt": "",
+        "created_at": "2021-05-03 10:20:30",
+        "updated_at": "2021-05-03 10:20:30",
+        "status": "published",
+        "author_id": "1"
+    }))
+    .unwrap()
+}
+
+fn page(id: u64, slug: &str, parent_id: Option<&str>) -> PageData {
+    PageData {
+        id,
+        title: slug.to_string(),
+        slug: slug.to_string(),
+        html: String::new(),
+        excerpt: String::new(),
+        created_at: String::from("2021-01-01 00:00:00"),
+        updated_at: String::from("2021-01-01 00:00:00"),
+        status: PostStatus::Publish,
+        author_id: String::from("1"),
+        image_url: None,
+        parent_id: parent_id.map(String::from),
+        menu_order: 0,
+    }
+}
+
+#[test]
+fn test_post_path_from_permalink_structure() {
+    let post = post();
+    assert_eq!(post_path("/%year%/%monthnum%/%postname%/", &post, None, None), "/2021/05/greve-geral/");
+    assert_eq!(
+        post_path("/%category%/%post_id%-%postname%.html", &post, Some("lutas/greves"), None),
+        "/lutas/greves/12-greve-geral.html"
+    );
+    assert_eq!(post_path("", &post, None, None), "/?p=12");
+}
+
+#[test]
+fn test_page_path_includes_parents() {
+    let parent = page(1, "sobre", None);
+    let child = page(2, "equipe", Some("1"));
+    let pages: HashMap<String, &PageData> = [(String::from("1"), &parent), (String::from("2"), &child)].into();
+    assert_eq!(page_path("/%postname%/", &child, &pages), "/sobre/equipe/");
+    assert_eq!(page_path("", &child, &pages), "/?page_id=2");
+}
+
+#[test]
+fn test_archive_paths_follow_bases() {
+    let tag = |taxonomy: Taxonomy| TagData {
+        id: 4,
+        name: String::from("Greve"),
+        slug: String::from("greve"),
+        description: String::new(),
+        count: 1,
+        parent_id: None,
+        taxonomy,
+    };
+    let author = AuthorPost {
+        id: 3,
+        name: String::from("Maria"),
+        email: String::new(),
+        login: String::from("Maria.Silva"),
+        nicename: String::from("maria-silva"),
+        role: None,
+        password: String::new(),
+        created_at: String::new(),
+        image_url: None,
+    };
+
+    let default = Permalinks {
+        structure: String::from("/%postname%/"),
+        ..Permalinks::default()
+    };
+    assert_eq!(default.term_path(&tag(Taxonomy::Category), Some("lutas/greve")), "/category/lutas/greve/");
+    assert_eq!(default.term_path(&tag(Taxonomy::PostTag), None), "/tag/greve/");
+    assert_eq!(default.author_path(&author), "/author/maria-silva/");
+
+    let custom = Permalinks {
+        structure: String::from("/%postname%.html"),
+        category_base: String::from("/temas/"),
+        tag_base: String::from("assunto"),
+    };
+    assert_eq!(custom.term_path(&tag(Taxonomy::Category), None), "/temas/greve");
+    assert_eq!(custom.term_path(&tag(Taxonomy::PostTag), None), "/assunto/greve");
+
+    let plain = Permalinks::default();
+    assert_eq!(plain.term_path(&tag(Taxonomy::Category), None), "/?cat=4");
+    assert_eq!(plain.term_path(&tag(Taxonomy::PostTag), None), "/?tag=greve");
+    assert_eq!(plain.author_path(&author), "/?author=3");
+}
+
+#[test]
+fn test_nicename_from_login() {
+    assert_eq!(nicename("maria"), "maria");
+    assert_eq!(nicename("Maria.Silva"), "maria-silva");
+    assert_eq!(nicename("João da Silva"), "joao-da-silva");
+    assert_eq!(nicename("maria@blog.com"), "mariablog-com");
+    assert_eq!(nicename("ana_luz"), "ana_luz");
+}
+
+#[test]
+fn test_redirect_files() {
+    let redirects = vec![
+        Redirect {
+            from: String::from("/2021/05/greve-geral/"),
+            to: String::from("/greve-geral/"),
+        },
+        Redirect {
+            from: String::from("/?p=7"),
+            to: String::from("/ato/"),
+        },
+    ];
+
+    assert_eq!(
+        ghost_yaml(&redirects),
+        "301:\n  '^/2021/05/greve\\-geral/?$': '/greve-geral/'\n  '^/\\?p=7$': '/ato/'\n\n302:\n"
+    );
+    assert!(nginx_map(&redirects).contains("    \"~^/2021/05/greve\\-geral/?(\\?.*)?$\" /greve-geral/;\n"));
+    assert!(nginx_map(&redirects).contains("    \"~^/\\?p=7(&.*)?$\" /ato/;\n"));
+    assert_eq!(
+        htaccess(&redirects),
+        "RewriteEngine On\n\
+         RewriteRule ^2021/05/greve\\-geral/?$ /greve-geral/ [R=301,L]\n\
+         RewriteCond %{QUERY_STRING} (^|&)p=7(&|$)\n\
+         RewriteRule ^$ /ato/? [R=301,L]\n"
+    );
+}
@@ -0,0 +1,84 @@
+use killer::tags::{category_paths, flatten_terms, TagData, TagMapping, TagRole, Taxonomy};
+
+fn term(id: i32, name: &str, taxonomy: Taxonomy) -> TagData {
+    TagData {
+        id,
+        name: name.to_string(),
+        slug: name.to_lowercase(),
+        description: String::new(),
+        count: 0,
+        parent_id: None,
+        taxonomy,
+    }
+}
+
+fn child(id: i32, name: &str, parent_id: i32) -> TagData {
+    TagData {
+        parent_id: Some(parent_id.to_string()),
+        ..term(id, name, Taxonomy::Category)
+    }
+}
+
+#[test]
+fn test_order_terms_puts_primary_first() {
+    let terms = vec![
+        term(1, "Greve", Taxonomy::PostTag),
+        term(2, "Nacional", Taxonomy::Category),
+        term(3, "Saúde", Taxonomy::PostTag),
+    ];
+
+    let ordered = TagMapping::default().order_terms(&terms);
+    let names: Vec<&str> = ordered.iter().map(|tag| tag.name.as_str()).collect();
+    assert_eq!(names, vec!["Nacional", "Greve", "Saúde"]);
+
+    let mapping = TagMapping {
+        categories: TagRole::Secondary,
+        post_tags: TagRole::Primary,
+        ..TagMapping::default()
+    };
+    let ordered = mapping.order_terms(&terms);
+    assert_eq!(ordered[0].id, 1);
+    assert_eq!(ordered[2].id, 2);
+}
+
+#[test]
+fn test_internal_and_skipped_tags() {
+    let mapping = TagMapping {
+        categories: TagRole::Primary,
+        post_tags: TagRole::Internal,
+        ..TagMapping::default()
+    };
+    let internal = mapping.apply(term(1, "Greve", Taxonomy::PostTag)).unwrap();
+    assert_eq!(internal.name, "#Greve");
+    assert_eq!(internal.slug, "hash-greve");
+
+    let mapping = TagMapping {
+        categories: TagRole::Primary,
+        post_tags: TagRole::Skip,
+        ..TagMapping::default()
+    };
+    assert!(mapping.apply(term(1, "Greve", Taxonomy::PostTag)).is_none());
+    let ordered = mapping.order_terms(&[
+        term(1, "Greve", Taxonomy::PostTag),
+        term(2, "Nacional", Taxonomy::Category),
+    ]);
+    assert_eq!(ordered.len(), 1);
+    assert_eq!(ordered[0].name, "Nacional");
+}
+
+#[test]
+fn test_flatten_category_hierarchy() {
+    let mut tags = vec![
+        child(3, "Lisboa", 2),
+        term(1, "Internacional", Taxonomy::Category),
+        child(2, "Europa", 1),
+    ];
+
+    let paths = category_paths(&tags);
+    assert_eq!(paths[&1], "Internacional");
+    assert_eq!(paths[&3], "Internacional / Europa / Lisboa");
+
+    flatten_terms(&mut tags, &paths);
+    assert_eq!(tags[2].name, "Internacional / Europa");
+    assert!(tags.iter().all(|tag| tag.parent_id.is_none()));
+}
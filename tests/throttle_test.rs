@@ -0,0 +1,43 @@
+use killer::throttle::Throttle;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[tokio::test]
+async fn test_throttle_limits_concurrency() {
+    let throttle = Arc::new(Throttle::new(2, None));
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+
+    let mut handles = vec![];
+    for _ in 0..6 {
+        let throttle = throttle.clone();
+        let running = running.clone();
+        let max_running = max_running.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = throttle.acquire().await;
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_throttle_limits_rate() {
+    let throttle = Throttle::new(10, Some(20.0));
+    let start = Instant::now();
+    for _ in 0..3 {
+        let _permit = throttle.acquire().await;
+    }
+
+    // 3 requisições a 20 req/s: a primeira sai na hora, as outras a cada 50ms
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
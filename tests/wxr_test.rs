@@ -0,0 +1,115 @@
+use killer::filter::{Filter, PostStatus};
+use killer::tags::Taxonomy;
+use killer::wxr::WxrDocument;
+use killer::Source;
+
+const WXR: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<rss version="2.0" xmlns:excerpt="http://wordpress.org/export/1.2/excerpt/" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:wp="http://wordpress.org/export/1.2/">
+<channel>
+    <title>Opinião</title>
+    <wp:author><wp:author_id>2</wp:author_id><wp:author_login><![CDATA[maria]]></wp:author_login><wp:author_email><![CDATA[maria@example.com]]></wp:author_email><wp:author_display_name><![CDATA[Maria Silva]]></wp:author_display_name></wp:author>
+    <wp:category><wp:term_id>5</wp:term_id><wp:category_nicename><![CDATA[nacional]]></wp:category_nicename><wp:category_parent><![CDATA[]]></wp:category_parent><wp:cat_name><![CDATA[Nacional]]></wp:cat_name></wp:category>
+    <wp:tag><wp:term_id>9</wp:term_id><wp:tag_slug><![CDATA[greve]]></wp:tag_slug><wp:tag_name><![CDATA[Greve]]></wp:tag_name></wp:tag>
+    <item>
+        <title>Greve &amp; luta</title>
+        <dc:creator><![CDATA[maria]]></dc:creator>
+        <content:encoded><![CDATA[<p>Texto</p><img src="https://site.org/wp-content/uploads/2021/05/a.jpg"> </item> no html]]></content:encoded>
+        <excerpt:encoded><![CDATA[]]></excerpt:encoded>
+        <wp:post_id>10</wp:post_id>
+        <wp:post_date><![CDATA[2021-05-03 10:00:00]]></wp:post_date>
+        <wp:post_name><![CDATA[greve-e-luta]]></wp:post_name>
+        <wp:status><![CDATA[publish]]></wp:status>
+        <wp:post_parent>0</wp:post_parent>
+        <wp:menu_order>0</wp:menu_order>
+        <wp:post_type><![CDATA[post]]></wp:post_type>
+        <category domain="category" nicename="nacional"><![CDATA[Nacional]]></category>
+        <category domain="post_tag" nicename="greve"><![CDATA[Greve]]></category>
+        <wp:postmeta><wp:meta_key><![CDATA[_thumbnail_id]]></wp:meta_key><wp:meta_value><![CDATA[11]]></wp:meta_value></wp:postmeta>
+    </item>
+    <item>
+        <title>a.jpg</title>
+        <wp:post_id>11</wp:post_id>
+        <wp:post_type><![CDATA[attachment]]></wp:post_type>
+        <wp:attachment_url><![CDATA[https://site.org/wp-content/uploads/2021/05/a.jpg]]></wp:attachment_url>
+        <wp:postmeta><wp:meta_key><![CDATA[_wp_attached_file]]></wp:meta_key><wp:meta_value><![CDATA[2021/05/a.jpg]]></wp:meta_value></wp:postmeta>
+    </item>
+</channel>
+</rss>"#;
+
+#[test]
+fn test_wxr_parse_channel() {
+    let document = WxrDocument::parse(WXR);
+
+    assert_eq!(document.authors.len(), 1);
+    assert_eq!(document.authors[0].login, "maria");
+    assert_eq!(document.authors[0].nicename, "maria");
+    assert_eq!(document.authors[0].display_name, "Maria Silva");
+    assert_eq!(document.categories[0].term_id, 5);
+    assert_eq!(document.categories[0].slug, "nacional");
+    assert_eq!(document.tags[0].name, "Greve");
+    assert_eq!(document.items.len(), 2);
+}
+
+#[test]
+fn test_wxr_parse_item() {
+    let document = WxrDocument::parse(WXR);
+    let post = &document.items[0];
+
+    assert_eq!(post.post_id, 10);
+    assert_eq!(post.title, "Greve & luta");
+    assert!(post.content.ends_with("</item> no html"));
+    assert_eq!(post.post_modified, "2021-05-03 10:00:00");
+    assert_eq!(post.terms.len(), 2);
+    assert_eq!(post.terms[1].domain, "post_tag");
+    assert_eq!(post.terms[1].slug, "greve");
+    assert_eq!(document.attachment_file(&post.meta["_thumbnail_id"]), Some(String::from("2021/05/a.jpg")));
+}
+
+#[tokio::test]
+async fn test_wxr_source_reads_categories_and_tags() {
+    let document = WxrDocument::parse(WXR);
+
+    let tags = document.tags().await.unwrap();
+    assert_eq!(tags.len(), 2);
+    assert_eq!(tags[1].taxonomy, Taxonomy::PostTag);
+
+    let posts = document.posts(&Filter::default()).await.unwrap();
+    let terms: Vec<(i32, Taxonomy)> = posts[0].terms.iter().map(|t| (t.id, t.taxonomy)).collect();
+    assert_eq!(terms, vec![(5, Taxonomy::Category), (9, Taxonomy::PostTag)]);
+}
+
+#[tokio::test]
+async fn test_wxr_source_applies_status_filter() {
+    let document = WxrDocument::parse(WXR);
+
+    let posts = document.posts(&Filter::default()).await.unwrap();
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].status, PostStatus::Publish);
+
+    let drafts = Filter {
+        statuses: vec![PostStatus::Draft, PostStatus::Pending],
+        ..Filter::default()
+    };
+    assert!(document.posts(&drafts).await.unwrap().is_empty());
+    assert!(document.authors(&drafts).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_wxr_source_applies_selection_filters() {
+    let document = WxrDocument::parse(WXR);
+    let count = |filter: Filter| {
+        let document = &document;
+        async move { document.posts(&filter).await.unwrap().len() }
+    };
+
+    assert_eq!(count(Filter { ids: vec![10], ..Filter::default() }).await, 1);
+    assert_eq!(count(Filter { ids: vec![11], ..Filter::default() }).await, 0);
+    assert_eq!(count(Filter { author: Some(2), ..Filter::default() }).await, 1);
+    assert_eq!(count(Filter { author: Some(3), ..Filter::default() }).await, 0);
+    assert_eq!(count(Filter { category: Some(String::from("nacional")), ..Filter::default() }).await, 1);
+    assert_eq!(count(Filter { category: Some(String::from("greve")), ..Filter::default() }).await, 0);
+    assert_eq!(
+        count(Filter { since: chrono::NaiveDate::from_ymd_opt(2021, 5, 4), ..Filter::default() }).await,
+        0
+    );
+}